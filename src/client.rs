@@ -0,0 +1,394 @@
+//! This module provides the `Client`, which holds default settings shared by many requests.
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::errors::NanoGetError;
+#[cfg(feature = "https")]
+use super::https::TlsSessions;
+use super::random::random_u64;
+use super::request::{validate_headers, Timeouts};
+use super::socket::SocketConfig;
+use super::{Request, Response};
+
+/// The overall timeout used by a `Client`, unless configured otherwise.
+const DEFAULT_CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The number of hosts that `Client::execute_all` sends requests to at the same time.
+const MAX_CONCURRENT_HOSTS: usize = 8;
+
+/// A `Client` holds default settings that every request executed through it inherits.
+///
+/// This saves setting the same options (like the timeouts) on every `Request`.
+///
+/// ## Precedence
+/// Settings made on the `Request` itself always take precedence. The settings of the `Client`
+/// are only used for the settings that the request leaves unset.
+///
+/// ## Timeouts
+/// Unlike a bare `Request`, which has no timeouts, a new `Client` has an overall timeout of 30 seconds
+/// so that a misbehaving server can't hang the caller forever.
+///
+/// ## Connections
+/// A `Client` does not keep connections open between requests. Each request opens its own
+/// connection and closes it once the response has been read, so there are no idle connections to
+/// close, and dropping a `Client` releases no sockets.
+///
+/// With the "https" feature, a `Client` does keep the TLS sessions it negotiated, and offers to
+/// resume them on later connections to the same host, which skips most of the TLS handshake. This
+/// is best-effort: a server that does not support resumption, or has forgotten the session, simply
+/// performs a full handshake. Clones of a `Client` share its sessions.
+///
+/// ## Accept
+/// Requests are sent with `Accept: */*` unless they set their own. A `Client` can change this
+/// default for the requests executed through it (see `Client::accept`). The precedence is: the
+/// `Accept` set on the request, then the `Accept` of the client, then the built-in `*/*`.
+///
+/// ## Request Ids
+/// A `Client` can add a header with a unique id to every request, to correlate the requests
+/// across services (see `Client::request_id_header`).
+///
+/// ## Example
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use nano_get::{Client, Request};
+///
+/// let mut client = Client::new();
+/// client.connect_timeout(Duration::from_secs(2));
+///
+/// let mut request = Request::default_get_request("http://example.com/").unwrap();
+/// // overrides the 30 second overall timeout of the client for this request.
+/// request.timeout(Duration::from_secs(60));
+/// let response = client.execute(&request).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Client {
+    timeouts: Timeouts,
+    socket_config: SocketConfig,
+    #[cfg(feature = "https")]
+    tls_sessions: Option<TlsSessions>,
+    accept: Option<String>,
+    request_id: Option<RequestIdHeader>,
+}
+
+/// The name of the request id header, along with the generator for its values.
+#[derive(Clone)]
+struct RequestIdHeader {
+    name: String,
+    generate: Arc<dyn Fn() -> String + Send + Sync>,
+}
+
+impl Debug for RequestIdHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "RequestIdHeader {{ name: {:?} }}", self.name)
+    }
+}
+
+impl Client {
+    /// Creates a new Client with an overall timeout of 30 seconds.
+    pub fn new() -> Self {
+        Client {
+            timeouts: Timeouts {
+                connect: None,
+                read: None,
+                overall: Some(DEFAULT_CLIENT_TIMEOUT),
+            },
+            socket_config: SocketConfig::default(),
+            #[cfg(feature = "https")]
+            tls_sessions: TlsSessions::new().ok(),
+            accept: None,
+            request_id: None,
+        }
+    }
+
+    /// Set the default connect timeout for requests. See `Request::connect_timeout`.
+    pub fn connect_timeout(&mut self, timeout: Duration) {
+        self.timeouts.connect = Some(timeout);
+    }
+
+    /// Set the default read timeout for requests. See `Request::read_timeout`.
+    pub fn read_timeout(&mut self, timeout: Duration) {
+        self.timeouts.read = Some(timeout);
+    }
+
+    /// Set the default overall timeout for requests. See `Request::timeout`.
+    pub fn timeout(&mut self, timeout: Duration) {
+        self.timeouts.overall = Some(timeout);
+    }
+
+    /// Set the default socket options for requests. See `Request::socket_config`.
+    pub fn socket_config(&mut self, config: SocketConfig) {
+        self.socket_config = config;
+    }
+
+    /// Set the default `Accept` header for requests, in place of the built-in `*/*`.
+    ///
+    /// A request that sets its own `Accept` header keeps it. As the built-in default can't be told
+    /// apart from a request setting `accept: */*` itself, such a request gets this default too.
+    ///
+    /// Like `Request::new`, this returns an error of kind `ParseError` if the value is not valid.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use nano_get::{Client, Request};
+    ///
+    /// let mut client = Client::new();
+    /// client.accept("application/json").unwrap();
+    /// let users = client.execute(&Request::default_get_request("http://example.com/users").unwrap()).unwrap();
+    /// ```
+    pub fn accept(&mut self, value: &str) -> Result<(), NanoGetError> {
+        validate_headers(Some(&vec![("accept", value)]))?;
+        self.accept = Some(value.to_string());
+        Ok(())
+    }
+
+    /// Add a header with the given name and a unique id (like `18f2c0a1b2c3d-00002a-5e1f...`) to
+    /// every request executed through this client.
+    ///
+    /// The ids are made up of the time, a counter and a random part. A request that already has
+    /// the header keeps its own value.
+    ///
+    /// Like `Client::accept`, this returns an error of kind `ParseError` if the name is not valid.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let mut client = nano_get::Client::new();
+    /// client.request_id_header("X-Request-Id").unwrap();
+    /// ```
+    pub fn request_id_header(&mut self, name: &str) -> Result<(), NanoGetError> {
+        self.request_id_header_with(name, generate_request_id)
+    }
+
+    /// Like `request_id_header`, but with the ids made by the given generator, which is called
+    /// once for every request. A generated id that is not a valid header value (like one with a
+    /// line break) is not sent, and the request goes without the header.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// let counter = AtomicUsize::new(0);
+    /// let mut client = nano_get::Client::new();
+    /// client.request_id_header_with("X-Request-Id", move || {
+    ///     format!("worker-1-{}", counter.fetch_add(1, Ordering::Relaxed))
+    /// }).unwrap();
+    /// ```
+    pub fn request_id_header_with<F>(&mut self, name: &str, generate: F) -> Result<(), NanoGetError>
+        where F: Fn() -> String + Send + Sync + 'static {
+        validate_headers(Some(&vec![(name, "")]))?;
+        self.request_id = Some(RequestIdHeader { name: name.to_string(), generate: Arc::new(generate) });
+        Ok(())
+    }
+
+    /// Executes the request, with the defaults of this client filled in for any settings that
+    /// the request does not set itself.
+    pub fn execute(&self, request: &Request) -> Result<Response, NanoGetError> {
+        self.prepare(request).execute()
+    }
+
+    /// Executes all the requests, returning their results in the same order as the requests.
+    ///
+    /// The requests are grouped by host and port. The requests to the same host are executed one
+    /// after the other, in the order given, while up to 8 hosts are sent requests at the same time,
+    /// each on its own thread. As a `Client` does not keep connections open (see the Connections
+    /// section above), each request still opens its own connection.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use nano_get::{Client, Request};
+    ///
+    /// let client = Client::new();
+    /// let requests = vec![
+    ///     Request::default_get_request("http://example.com/a").unwrap(),
+    ///     Request::default_get_request("http://example.org/").unwrap(),
+    ///     Request::default_get_request("http://example.com/b").unwrap(),
+    /// ];
+    /// for result in client.execute_all(requests) {
+    ///     println!("{:?}", result.map(|response| response.status));
+    /// }
+    /// ```
+    pub fn execute_all(&self, requests: Vec<Request>) -> Vec<Result<Response, NanoGetError>> {
+        let count = requests.len();
+        let mut groups: Vec<Vec<(usize, Request)>> = Vec::new();
+        let mut group_of_host = HashMap::new();
+        for (index, request) in requests.into_iter().enumerate() {
+            let host = (request.host().to_ascii_lowercase(), request.port());
+            let group = *group_of_host.entry(host).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[group].push((index, request));
+        }
+        let workers = groups.len().min(MAX_CONCURRENT_HOSTS);
+        let groups = Mutex::new(groups.into_iter());
+        let results = Mutex::new((0..count).map(|_| None).collect::<Vec<_>>());
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let group = groups.lock().unwrap().next();
+                    let Some(group) = group else { break };
+                    for (index, request) in group {
+                        let result = self.execute(&request);
+                        results.lock().unwrap()[index] = Some(result);
+                    }
+                });
+            }
+        });
+        results.into_inner().unwrap().into_iter().map(|result| result.unwrap()).collect()
+    }
+
+    fn prepare(&self, request: &Request) -> Request {
+        let mut request = request.clone();
+        request.timeouts = request.timeouts.or(self.timeouts);
+        request.socket_config = request.socket_config.or(&self.socket_config);
+        #[cfg(feature = "https")] {
+            request.tls_sessions = request.tls_sessions.take().or_else(|| self.tls_sessions.clone());
+        }
+        if let Some(accept) = self.accept.as_ref() {
+            if request.has_default_accept() {
+                request.add_header("accept", accept);
+            }
+        }
+        if let Some(request_id) = self.request_id.as_ref() {
+            if !request.get_request_headers().any(|(k, _)| k.eq_ignore_ascii_case(&request_id.name)) {
+                let id = (request_id.generate)();
+                if validate_headers(Some(&vec![(&request_id.name, &id)])).is_ok() {
+                    request.add_header(&request_id.name, &id);
+                }
+            }
+        }
+        request
+    }
+}
+
+/// Generates an id that is unique to this process, and very likely unique across processes.
+fn generate_request_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let micros = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_micros());
+    let random = random_u64();
+    format!("{:x}-{:06x}-{:016x}", micros, count & 0xff_ffff, random)
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    use super::*;
+    use crate::ErrorKind;
+    use crate::testing::serve_with;
+
+    /// Answers `count` requests, one connection at a time, with the path of each request as body.
+    fn serve_paths(count: usize) -> (u16, thread::JoinHandle<Vec<()>>) {
+        serve_with(count, |mut stream, _, head| {
+            let path = head.split(' ').nth(1).unwrap().to_string();
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", path.len(), path).unwrap();
+        })
+    }
+
+    fn request_id(request: &Request) -> Option<String> {
+        request.get_request_headers()
+            .find(|(k, _)| k.eq_ignore_ascii_case("x-request-id"))
+            .map(|(_, v)| v.to_string())
+    }
+
+    #[test]
+    fn test_request_id_header() {
+        let request = Request::default_get_request("http://example.com/").unwrap();
+        let mut client = Client::new();
+        assert_eq!(request_id(&client.prepare(&request)), None);
+        client.request_id_header("X-Request-Id").unwrap();
+        let first = request_id(&client.prepare(&request)).unwrap();
+        let second = request_id(&client.prepare(&request)).unwrap();
+        assert_ne!(first, second);
+        let mut own = request.clone();
+        own.add_header("x-request-id", "mine");
+        assert_eq!(request_id(&client.prepare(&own)).unwrap(), "mine");
+    }
+
+    #[test]
+    fn test_default_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let request = Request::default_get_request(format!("http://127.0.0.1:{}/", port)).unwrap();
+        let mut client = Client::new();
+        client.timeout(Duration::from_millis(100));
+        let started = Instant::now();
+        let err = client.execute(&request).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Timeout);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_request_timeouts_take_precedence() {
+        let secs = |secs| Some(Duration::from_secs(secs));
+        let request = Timeouts { connect: secs(1), read: None, overall: secs(60) };
+        let client = Timeouts { connect: secs(2), read: secs(3), overall: secs(30) };
+        let timeouts = request.or(client);
+        assert_eq!((timeouts.connect, timeouts.read, timeouts.overall), (secs(1), secs(3), secs(60)));
+        assert_eq!(Timeouts::default().or(client).overall, secs(30));
+    }
+
+    #[test]
+    fn test_accept() {
+        let accept = |request: &Request| request.get_request_headers()
+            .find(|(k, _)| k.eq_ignore_ascii_case("accept"))
+            .map(|(_, v)| v.to_string());
+        let request = Request::default_get_request("http://example.com/").unwrap();
+        let mut client = Client::new();
+        assert_eq!(accept(&client.prepare(&request)).unwrap(), "*/*");
+        client.accept("application/json").unwrap();
+        assert_eq!(accept(&client.prepare(&request)).unwrap(), "application/json");
+        let mut own = request.clone();
+        own.add_header("Accept", "text/csv");
+        assert_eq!(accept(&client.prepare(&own)).unwrap(), "text/csv");
+        assert!(client.accept("bad\r\nvalue").is_err());
+    }
+
+    #[test]
+    fn test_execute_all_keeps_the_order_of_the_requests() {
+        let (first_port, first) = serve_paths(3);
+        let (second_port, second) = serve_paths(1);
+        let paths = [(first_port, "/a"), (second_port, "/b"), (first_port, "/c"), (1, "/d"), (first_port, "/e")];
+        let requests = paths.iter()
+            .map(|(port, path)| Request::default_get_request(format!("http://127.0.0.1:{}{}", port, path)).unwrap())
+            .collect();
+        let results = Client::new().execute_all(requests);
+        let bodies: Vec<_> = results.iter().map(|result| result.as_ref().ok().map(|r| r.body.as_str())).collect();
+        assert_eq!(bodies, vec![Some("/a"), Some("/b"), Some("/c"), None, Some("/e")]);
+        first.join().unwrap();
+        second.join().unwrap();
+        assert!(Client::new().execute_all(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_request_id_header_with_generator() {
+        let request = Request::default_get_request("http://example.com/").unwrap();
+        let mut client = Client::new();
+        client.request_id_header_with("X-Request-Id", || "fixed".to_string()).unwrap();
+        assert_eq!(request_id(&client.prepare(&request)).unwrap(), "fixed");
+    }
+
+    #[test]
+    fn test_request_id_header_rejects_line_breaks() {
+        let request = Request::default_get_request("http://example.com/").unwrap();
+        let mut client = Client::new();
+        assert!(client.request_id_header("X-Id\r\nEvil: 1").is_err());
+        assert_eq!(request_id(&client.prepare(&request)), None);
+        client.request_id_header_with("X-Request-Id", || "id\r\nEvil: 1".to_string()).unwrap();
+        let prepared = client.prepare(&request);
+        assert_eq!(request_id(&prepared), None);
+        assert!(!prepared.get_request_headers().any(|(k, _)| k.eq_ignore_ascii_case("evil")));
+    }
+}
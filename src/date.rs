@@ -0,0 +1,103 @@
+//! This module provides parsing of the HTTP-date format used by headers like `Date` and `Retry-After`.
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// Parses a HTTP-date as per [RFC 7231](https://tools.ietf.org/html/rfc7231#section-7.1.1.1).
+///
+/// Accepts the preferred IMF-fixdate format (`Sun, 06 Nov 1994 08:49:37 GMT`) as well as the obsolete
+/// RFC 850 (`Sunday, 06-Nov-94 08:49:37 GMT`) and asctime (`Sun Nov  6 08:49:37 1994`) formats.
+///
+/// Returns `None` if the date is not valid.
+pub fn parse_http_date(s: &str) -> Option<SystemTime> {
+    let s = s.trim();
+    let (day, month, year, time) = if let Some((_, rest)) = s.split_once(", ") {
+        let parts: Vec<&str> = rest.split(' ').collect();
+        match parts.as_slice() {
+            [day, month, year, time, "GMT"] => (*day, *month, *year, *time),
+            [date, time, "GMT"] => {
+                let date: Vec<&str> = date.split('-').collect();
+                match date.as_slice() {
+                    [day, month, year] => (*day, *month, *year, *time),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        }
+    } else {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        match parts.as_slice() {
+            [_, month, day, time, year] => (*day, *month, *year, *time),
+            _ => return None,
+        }
+    };
+    let day: u64 = parse_digits(day, 1, 2)?;
+    let month = MONTHS.iter().position(|m| *m == month)? as u64 + 1;
+    let year = match year.len() {
+        // RFC 850 two-digit years are interpreted as in RFC 7231: 70-99 as 19xx, else as 20xx.
+        2 => match parse_digits(year, 2, 2)? {
+            y if y >= 70 => 1900 + y,
+            y => 2000 + y,
+        },
+        _ => parse_digits(year, 4, 4)?,
+    };
+    let time: Vec<&str> = time.split(':').collect();
+    let (hour, minute, second) = match time.as_slice() {
+        [h, m, s] => (parse_digits(h, 2, 2)?, parse_digits(m, 2, 2)?, parse_digits(s, 2, 2)?),
+        _ => return None,
+    };
+    if year < 1970 || day == 0 || day > days_in_month(year, month) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    let seconds = days_since_epoch(year, month, day) * 86400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+fn parse_digits(s: &str, min_len: usize, max_len: usize) -> Option<u64> {
+    if s.len() < min_len || s.len() > max_len || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+fn is_leap_year(year: u64) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn days_since_epoch(year: u64, month: u64, day: u64) -> u64 {
+    let days_before_year: u64 = (1970..year).map(|y| if is_leap_year(y) { 366 } else { 365 }).sum();
+    let days_before_month: u64 = (1..month).map(|m| days_in_month(year, m)).sum();
+    days_before_year + days_before_month + day - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_http_date_formats() {
+        let expected = UNIX_EPOCH + Duration::from_secs(784111777);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(expected));
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), Some(expected));
+        assert_eq!(parse_http_date("Sun Nov  6 08:49:37 1994"), Some(expected));
+        assert_eq!(parse_http_date("Thu, 29 Feb 2024 00:00:00 GMT"), Some(UNIX_EPOCH + Duration::from_secs(1709164800)));
+    }
+
+    #[test]
+    fn test_parse_http_date_invalid() {
+        for date in &["", "yesterday", "Sun, 06 Nov 1994 08:49:37 PST", "Sun, 32 Nov 1994 08:49:37 GMT",
+            "Thu, 29 Feb 2023 00:00:00 GMT", "Sun, 06 Foo 1994 08:49:37 GMT", "Sun, 06 Nov 1994 24:00:00 GMT",
+            "Sun, 06 Nov 1960 08:49:37 GMT", "Sun, 06 Nov 1994 8:49:37 GMT"] {
+            assert_eq!(parse_http_date(date), None, "{}", date);
+        }
+    }
+}
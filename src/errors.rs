@@ -1,20 +1,37 @@
+use std::error::Error;
 use std::fmt::Formatter;
+use std::io;
 
 #[derive(Debug)]
 pub struct NanoGetError {
     kind: ErrorKind,
+    source: Option<Box<dyn Error + Send + Sync>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum ErrorKind {
     Default,
     ParseError,
     NetworkError,
+    DnsError,
     HttpMethodError,
     HttpsSslError,
+    Timeout,
+    Cancelled,
+    ProxyError,
+    BlockedAddress,
+    BodyTooLarge,
+    InsecureRedirect,
+    /// The response has a status that the request does not accept (see `Request::expect_status`),
+    /// with its code (0 if the status line had no valid code).
+    HttpStatus(u16),
 }
 
-impl std::error::Error for NanoGetError {}
+impl Error for NanoGetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_ref().map(|source| source.as_ref() as &(dyn Error + 'static))
+    }
+}
 
 impl std::fmt::Display for NanoGetError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result<> {
@@ -25,6 +42,65 @@ impl std::fmt::Display for NanoGetError {
 
 impl NanoGetError {
     pub fn new(kind: ErrorKind) -> Self {
-        NanoGetError { kind }
+        NanoGetError { kind, source: None }
+    }
+
+    /// Creates an error of the given kind, caused by the given underlying error.
+    ///
+    /// The underlying error is returned by `source()`.
+    pub fn with_source<E: Into<Box<dyn Error + Send + Sync>>>(kind: ErrorKind, source: E) -> Self {
+        NanoGetError { kind, source: Some(source.into()) }
+    }
+
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
     }
-}
\ No newline at end of file
+}
+
+/// Converts the error into an `io::Error` of the closest kind, keeping the error as its inner error.
+///
+/// A `Timeout` becomes `TimedOut`, a `ParseError` becomes `InvalidData`, a `DnsError` becomes
+/// `NotFound`, a `HttpMethodError` becomes `InvalidInput`, a `Cancelled` becomes `Interrupted` and a
+/// `BlockedAddress` becomes `PermissionDenied`.
+/// Everything else becomes `Other`.
+///
+/// ## Example
+/// ```rust
+/// use nano_get::{ErrorKind, NanoGetError};
+///
+/// fn fetch() -> std::io::Result<()> {
+///     Err(NanoGetError::new(ErrorKind::Timeout))?
+/// }
+/// assert_eq!(fetch().unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+/// ```
+impl From<NanoGetError> for io::Error {
+    fn from(err: NanoGetError) -> Self {
+        let kind = match err.kind {
+            ErrorKind::Timeout => io::ErrorKind::TimedOut,
+            ErrorKind::ParseError => io::ErrorKind::InvalidData,
+            ErrorKind::DnsError => io::ErrorKind::NotFound,
+            ErrorKind::HttpMethodError => io::ErrorKind::InvalidInput,
+            ErrorKind::Cancelled => io::ErrorKind::Interrupted,
+            ErrorKind::BlockedAddress => io::ErrorKind::PermissionDenied,
+            ErrorKind::Default | ErrorKind::NetworkError | ErrorKind::HttpsSslError | ErrorKind::ProxyError
+            | ErrorKind::BodyTooLarge | ErrorKind::InsecureRedirect | ErrorKind::HttpStatus(_) => {
+                io::ErrorKind::Other
+            }
+        };
+        io::Error::new(kind, err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_io_error() {
+        let err = io::Error::from(NanoGetError::new(ErrorKind::ParseError));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let inner = err.into_inner().unwrap().downcast::<NanoGetError>().unwrap();
+        assert_eq!(inner.kind(), &ErrorKind::ParseError);
+    }
+}
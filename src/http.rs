@@ -1,11 +1,20 @@
 //! This module provides the main HTTP Get method.
-use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::convert::TryFrom;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use super::errors::NanoGetError;
+use super::errors::{ErrorKind, NanoGetError};
 use super::Request;
-use super::response::{new_response_from_complete, Response};
-use super::ToUrl;
+use super::request::{BodyReader, USER_AGENT};
+#[cfg(feature = "log")]
+use super::request::redact_header_value;
+use super::response::{get_body_length, has_bare_lf, is_chunked, is_keep_alive, new_response_from_parts, parse_status,
+                      validate_head, InformationalResponse, RawExchange, Response, ResponseStatus};
+use super::socket::{self, SocketConfig};
+use super::stream::ResponseLines;
+use super::{ToUrl, Url};
 
 /// The basic implementation of the HTTP GET method.
 ///
@@ -20,56 +29,1670 @@ pub fn get_http<A: ToUrl>(url: A) -> String {
 }
 
 pub fn request_http_get(request: &Request) -> Result<Response, NanoGetError> {
-    let mut stream = TcpStream::connect(request.url.get_host_with_port()).unwrap();
-    execute(&mut stream, &request)
+    let deadline = request.get_deadline();
+    let stream = connect(request, deadline)?;
+    let peer_addr = stream.peer_addr().ok();
+    let mut response = execute(timed(stream, request, deadline)?, request, deadline)?;
+    response.set_peer_addr(peer_addr);
+    Ok(response)
+}
+
+pub fn request_http_get_into(request: &Request, body: &mut Vec<u8>) -> Result<ResponseStatus, NanoGetError> {
+    let deadline = request.get_deadline();
+    let stream = connect(request, deadline)?;
+    execute_into(timed(stream, request, deadline)?, request, deadline, body)
+}
+
+pub fn request_http_lines(request: &Request) -> Result<ResponseLines, NanoGetError> {
+    let deadline = request.get_deadline();
+    let stream = connect(request, deadline)?;
+    execute_lines(stream, request, deadline)
+}
+
+pub fn request_http_head_only(request: &Request) -> Result<Response, NanoGetError> {
+    let deadline = request.get_deadline();
+    let stream = connect(request, deadline)?;
+    let peer_addr = stream.peer_addr().ok();
+    let mut response = execute_head_only(timed(stream, request, deadline)?, request, deadline)?;
+    response.set_peer_addr(peer_addr);
+    Ok(response)
+}
+
+pub fn request_http_get_to_writer(request: &Request, writer: &mut dyn Write) -> Result<ResponseStatus, NanoGetError> {
+    let deadline = request.get_deadline();
+    let stream = connect(request, deadline)?;
+    execute_to_writer(timed(stream, request, deadline)?, request, deadline, writer)
+}
+
+/// Opens the TCP connection for the request, applying the connect and read timeouts of the request.
+///
+/// This resolves the host of the request (see `resolve`), unless it was already resolved earlier in
+/// the same `Request::execute`, and connects to it (see `connect_tcp`).
+pub fn connect(request: &Request, deadline: Option<Instant>) -> Result<TcpStream, NanoGetError> {
+    if let Some(addrs) = request.get_resolved() {
+        return connect_tcp(addrs, request, deadline);
+    }
+    let addrs = resolve(&request.url)?;
+    connect_tcp(&addrs, request, deadline)
+}
+
+/// Resolves the host of the url to the addresses to connect to.
+///
+/// Fails with an error of kind `DnsError` if the host can't be resolved.
+///
+/// ## Example
+/// ```rust
+/// use nano_get::{resolve, Url};
+/// let addrs = resolve(&Url::new("http://127.0.0.1:8080/")).unwrap();
+/// assert_eq!(addrs[0].port(), 8080);
+/// ```
+pub fn resolve(url: &Url) -> Result<Vec<SocketAddr>, NanoGetError> {
+    let addrs: Vec<SocketAddr> = url.get_host_with_port().to_socket_addrs()
+        .map_err(|err| NanoGetError::with_source(ErrorKind::DnsError, err))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(NanoGetError::new(ErrorKind::DnsError));
+    }
+    Ok(addrs)
+}
+
+/// Opens a TCP connection to the first of the addresses that accepts it, applying the connect and
+/// read timeouts of the request.
+///
+/// The addresses are tried in order. The connect timeout of the request bounds the attempts as a
+/// whole, including any retries: each attempt is given the time that is left, so a host with many
+/// unreachable addresses fails once the timeout elapses, rather than after waiting out the timeout
+/// for each of them. If the timeout elapses, or the deadline (if given) passes, this fails with an
+/// error of kind `Timeout`, whose source tells how many of the addresses were tried. Other
+/// failures to connect are errors of kind `NetworkError`.
+///
+/// If the request blocks private addresses (see `Request::block_private_addresses`) and any of the
+/// addresses is private, this fails with an error of kind `BlockedAddress` without connecting.
+///
+/// If the connection is refused, the connect is retried as configured by
+/// `Request::connect_retries`. Nothing has been sent at that point, so only the connect is repeated.
+///
+/// ## Example
+/// ```rust,no_run
+/// use std::io::Write;
+/// use nano_get::{connect_tcp, resolve, Request};
+///
+/// let request = Request::default_get_request("http://example.com/").unwrap();
+/// let addrs = resolve(&request.url).unwrap();
+/// let mut stream = connect_tcp(&addrs, &request, None).unwrap();
+/// stream.write_all(b"PING\r\n").unwrap();
+/// ```
+pub fn connect_tcp(addrs: &[SocketAddr], request: &Request, deadline: Option<Instant>) -> Result<TcpStream, NanoGetError> {
+    if request.blocks_private_addresses() && addrs.iter().any(|addr| is_private_address(addr.ip())) {
+        return Err(NanoGetError::new(ErrorKind::BlockedAddress));
+    }
+    let (retries, delay) = request.get_connect_retries();
+    let connect_deadline = earliest(deadline, request.timeouts.connect.map(|timeout| Instant::now() + timeout));
+    let mut attempt = 0;
+    loop {
+        match connect_any(addrs, connect_deadline, &request.socket_config) {
+            Ok(stream) => {
+                let timeout = remaining(request.timeouts.read, deadline).map_err(io_error)?;
+                stream.set_read_timeout(timeout).map_err(io_error)?;
+                stream.set_write_timeout(timeout).map_err(io_error)?;
+                return Ok(stream);
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::ConnectionRefused && attempt < retries => {
+                attempt += 1;
+                thread::sleep(delay);
+            }
+            Err(err) => return Err(io_error(err)),
+        }
+    }
+}
+
+/// Opens a tunnel to the target (like `example.com:22`) through the HTTP proxy, using the `CONNECT`
+/// method, and returns the connection to the proxy for the caller to speak any protocol over.
+///
+/// Fails with an error of kind `ProxyError` if the proxy does not answer with a 2xx status, and
+/// `ParseError` if the target is not a valid `host:port` or the response of the proxy is malformed.
+pub fn connect_tunnel(proxy: &Url, target: &str) -> Result<TcpStream, NanoGetError> {
+    if target.is_empty() || !target.bytes().all(|b| b.is_ascii_graphic()) || !target.contains(':') {
+        return Err(NanoGetError::new(ErrorKind::ParseError));
+    }
+    let request = Request::default_get_request(proxy).map_err(|_err| NanoGetError::new(ErrorKind::ParseError))?;
+    let mut stream = connect(&request, None)?;
+    write!(stream, "CONNECT {target} HTTP/1.1\r\nHost: {target}\r\nUser-Agent: {agent}\r\n\r\n",
+           target = target, agent = USER_AGENT).map_err(io_error)?;
+    let head = read_tunnel_head(&mut stream)?;
+    let status = parse_status(&head);
+    if !status.0.is_success() {
+        let status_line = String::from_utf8_lossy(&head).lines().next().unwrap_or_default().to_string();
+        return Err(NanoGetError::with_source(ErrorKind::ProxyError,
+                                             format!("the proxy refused the tunnel: {}", status_line)));
+    }
+    Ok(stream)
+}
+
+/// Reads the head of the response to a `CONNECT` one byte at a time, so that nothing the target
+/// sends after it is consumed.
+fn read_tunnel_head(stream: &mut dyn Read) -> Result<Vec<u8>, NanoGetError> {
+    const MAX_HEAD_LEN: usize = 64 * 1024;
+    let mut head = Vec::new();
+    let mut byte = [0u8];
+    while !head.ends_with(b"\r\n\r\n") {
+        if stream.read(&mut byte).map_err(io_error)? == 0 || head.len() >= MAX_HEAD_LEN {
+            return Err(NanoGetError::new(ErrorKind::ParseError));
+        }
+        head.push(byte[0]);
+    }
+    Ok(head)
+}
+
+/// Checks if the address is a loopback, link-local, private (RFC 1918), unique-local or unspecified
+/// address, including IPv4 addresses mapped into IPv6.
+fn is_private_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified(),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_private_address(IpAddr::V4(ip)),
+            None => ip.is_loopback() || ip.is_unspecified() || ip.is_unique_local() || ip.is_unicast_link_local(),
+        },
+    }
+}
+
+/// Tries each of the addresses in turn, until one accepts the connection. Each attempt is given the
+/// time left until the deadline, so that it bounds the connect as a whole rather than each attempt.
+fn connect_any(addrs: &[SocketAddr], deadline: Option<Instant>, config: &SocketConfig) -> io::Result<TcpStream> {
+    let mut last_err = io::Error::new(io::ErrorKind::InvalidInput, "host did not resolve to any address");
+    for (tried, addr) in addrs.iter().enumerate() {
+        let result = match remaining(None, deadline) {
+            Ok(timeout) => socket::connect(addr, timeout, config),
+            Err(_) => return Err(connect_timed_out(tried, addrs.len())),
+        };
+        match result {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_err = err,
+        }
+    }
+    if last_err.kind() == io::ErrorKind::TimedOut {
+        return Err(connect_timed_out(addrs.len(), addrs.len()));
+    }
+    Err(last_err)
+}
+
+fn connect_timed_out(tried: usize, total: usize) -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, format!("connect timed out after trying {} of {} addresses", tried, total))
+}
+
+/// Returns the shorter of the timeout and the time left until the deadline.
+///
+/// Fails with `TimedOut` if the deadline has already passed.
+fn remaining(timeout: Option<Duration>, deadline: Option<Instant>) -> io::Result<Option<Duration>> {
+    match deadline {
+        Some(deadline) => {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left == Duration::from_secs(0) {
+                return Err(io::Error::from(io::ErrorKind::TimedOut));
+            }
+            Ok(Some(timeout.map_or(left, |timeout| timeout.min(left))))
+        }
+        None => Ok(timeout),
+    }
+}
+
+/// Sends the request over the stream and reads the response.
+///
+/// If a deadline is given, this fails with an error of kind `Timeout` once it passes.
+pub fn execute<S: Read + Write>(stream: S, request: &Request, deadline: Option<Instant>) -> Result<Response, NanoGetError> {
+    let mut body = Vec::new();
+    if request.get_capture_exchange() {
+        let mut stream = CapturingStream { stream, exchange: RawExchange::default() };
+        let received = exchange(&mut stream, request, deadline, BodySink::Buffer(&mut body))?;
+        let mut response = build_response(received, body, request);
+        response.set_raw_exchange(stream.exchange);
+        return Ok(response);
+    }
+    let received = exchange(stream, request, deadline, BodySink::Buffer(&mut body))?;
+    Ok(build_response(received, body, request))
+}
+
+/// Sends the request over the stream and reads the body of the response into the buffer, which is
+/// cleared first. Returns the status of the response.
+///
+/// If a deadline is given, this fails with an error of kind `Timeout` once it passes.
+pub fn execute_into<S: Read + Write>(stream: S, request: &Request, deadline: Option<Instant>,
+                                     body: &mut Vec<u8>) -> Result<ResponseStatus, NanoGetError> {
+    body.clear();
+    let received = exchange(stream, request, deadline, BodySink::Buffer(body))?;
+    Ok(parse_status(&received.head))
+}
+
+/// Sends the request over the stream and copies the body of the response to the writer as it is
+/// read, without buffering it. Returns the status of the response.
+///
+/// If a deadline is given, this fails with an error of kind `Timeout` once it passes.
+pub fn execute_to_writer<S: Read + Write>(stream: S, request: &Request, deadline: Option<Instant>,
+                                          writer: &mut dyn Write) -> Result<ResponseStatus, NanoGetError> {
+    let received = exchange(stream, request, deadline, BodySink::Writer(writer))?;
+    Ok(parse_status(&received.head))
+}
+
+/// Sends the request over the stream and reads the head of the response, returning an iterator
+/// over the lines of the body that reads them off the stream as they arrive.
+///
+/// The deadline only applies to reading the head.
+pub fn execute_lines<S: Read + Write + Send + 'static>(mut stream: S, request: &Request,
+                                                       deadline: Option<Instant>) -> Result<ResponseLines, NanoGetError> {
+    check_cancelled(request)?;
+    send_request(&mut stream, request).map_err(io_error)?;
+    let stream = LimitedStream { stream, remaining: request.get_max_response_bytes() };
+    let mut reader: BufReader<Box<dyn Read + Send>> = BufReader::new(Box::new(stream));
+    let head = read_final_head(&mut reader, request, deadline, &mut Vec::new())?;
+    Ok(ResponseLines::new(&head, reader))
+}
+
+/// Sends the request over the stream and reads only the head of the response, returning it as a
+/// `Response` with an empty body. The stream is dropped (closing the connection) without reading
+/// the body.
+pub fn execute_head_only<S: Read + Write>(stream: S, request: &Request,
+                                          deadline: Option<Instant>) -> Result<Response, NanoGetError> {
+    if request.get_capture_exchange() {
+        let mut stream = CapturingStream { stream, exchange: RawExchange::default() };
+        let mut response = read_head_only(&mut stream, request, deadline)?;
+        response.set_raw_exchange(stream.exchange);
+        return Ok(response);
+    }
+    read_head_only(stream, request, deadline)
+}
+
+fn read_head_only<S: Read + Write>(mut stream: S, request: &Request,
+                                   deadline: Option<Instant>) -> Result<Response, NanoGetError> {
+    check_cancelled(request)?;
+    send_request(&mut stream, request).map_err(io_error)?;
+    let mut reader = BufReader::new(LimitedStream { stream, remaining: request.get_max_response_bytes() });
+    let mut interim = Vec::new();
+    let head = read_final_head(&mut reader, request, deadline, &mut interim)?;
+    let bytes_received = head.len() as u64;
+    Ok(build_response(ReceivedHead { head, trailers: None, bytes_received, interim }, Vec::new(), request))
 }
 
-pub fn execute<S: Read + Write>(mut stream: S, request: &Request) -> Result<Response, NanoGetError> {
-    send_request(&mut stream, &request).unwrap();
-    receive_response(&mut stream)
+/// Sends the request and reads the response.
+///
+/// If the request expects `100 Continue` (see `Request::expect_continue`) and has a body from a
+/// reader, only the head of the request is sent at first. The body follows once the server accepts
+/// it, and is never sent if the server answers with a final response right away.
+fn exchange<S: Read + Write>(stream: S, request: &Request, deadline: Option<Instant>,
+                             mut body: BodySink) -> Result<ReceivedHead, NanoGetError> {
+    check_cancelled(request)?;
+    let started = Instant::now();
+    let mut stream = BufReader::new(LimitedStream { stream, remaining: request.get_max_response_bytes() });
+    let mut interim = Vec::new();
+    let head = match request.get_body_reader() {
+        Some(body_reader) if request.get_expect_continue() => {
+            send_head(stream.get_mut(), request).map_err(io_error)?;
+            let head = await_continue(&mut stream, request, deadline, &mut interim)?;
+            if head.is_none() {
+                send_body_from_reader(stream.get_mut(), request, body_reader).map_err(io_error)?;
+            }
+            head
+        }
+        _ => {
+            send_request(stream.get_mut(), request).map_err(io_error)?;
+            None
+        }
+    };
+    let head = match head {
+        Some(head) => head,
+        None => read_final_head(&mut stream, request, deadline, &mut interim)?,
+    };
+    let mut received = receive_body(&mut stream, head, request, deadline, &mut body)?;
+    received.interim = interim;
+    log_response(&received, started);
+    Ok(received)
 }
 
+/// Sends the request over the stream.
+///
+/// The request is written through a buffer of `Request::write_buffer_size` bytes, so that the
+/// request line, headers and body take as few writes as the buffer allows. The body is written
+/// separately if the request turns off `Request::coalesce_writes`.
 pub fn send_request(stream: &mut dyn Write, request: &Request) -> std::io::Result<()> {
-    write_http_method(stream, request)?;
-    write_std_headers(stream, request)?;
-    if request.body.is_some() {
-        return write_request_body(stream, request);
+    let mut writer = BufWriter::with_capacity(request.get_write_buffer_size(), stream);
+    write_head(&mut writer, request)?;
+    if let Some(body_reader) = request.get_body_reader() {
+        write_body_from_reader(&mut writer, body_reader)?;
+    } else if let Some(body) = request.get_body_bytes() {
+        if !request.get_coalesce_writes() {
+            writer.flush()?;
+        }
+        writer.write_all(body)?;
     }
-    Ok(())
+    writer.flush()
+}
+
+/// Sends only the request line and headers, leaving the body to be sent later.
+fn send_head(stream: &mut dyn Write, request: &Request) -> std::io::Result<()> {
+    let mut writer = BufWriter::with_capacity(request.get_write_buffer_size(), stream);
+    write_head(&mut writer, request)?;
+    writer.flush()
+}
+
+/// Sends the body from the reader of the request, after its head was sent with `send_head`.
+fn send_body_from_reader(stream: &mut dyn Write, request: &Request, body_reader: &BodyReader) -> std::io::Result<()> {
+    let mut writer = BufWriter::with_capacity(request.get_write_buffer_size(), stream);
+    write_body_from_reader(&mut writer, body_reader)?;
+    writer.flush()
+}
+
+fn write_head(stream: &mut dyn Write, request: &Request) -> std::io::Result<()> {
+    let headers = request.effective_headers();
+    log_request(request, &headers);
+    write_http_method(stream, request)?;
+    write_std_headers(stream, &headers)
 }
 
 fn write_http_method(stream: &mut dyn Write, request: &Request) -> std::io::Result<()> {
-    stream.write_fmt(format_args!("{method} {path} HTTP/1.1\r\n",
-                                  method = request.get_request_type(),
-                                  path = request.url.path))?;
-    Ok(())
+    write!(stream, "{}\r\n", request.request_line())
 }
 
-fn write_std_headers(stream: &mut dyn Write, request: &Request) -> std::io::Result<()> {
-    for (k, v) in request.get_request_headers() {
+fn write_std_headers(stream: &mut dyn Write, headers: &[(String, String)]) -> std::io::Result<()> {
+    for (k, v) in headers {
         writeln!(stream, "{}: {}\r", k, v)?;
     }
     stream.write_all(b"\r\n")?;
     Ok(())
 }
 
-fn write_request_body(stream: &mut dyn Write, request: &Request) -> std::io::Result<()> {
-    write!(stream, "{}", request.body.as_ref().unwrap())
+fn write_body_from_reader(stream: &mut dyn Write, body_reader: &BodyReader) -> io::Result<()> {
+    let mut reader = body_reader.reader.lock()
+        .map_err(|_err| io::Error::other("the body reader was poisoned"))?;
+    match body_reader.length {
+        Some(length) => {
+            let copied = io::copy(&mut (&mut *reader).take(length), stream)?;
+            if copied < length {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the body was shorter than its length"));
+            }
+            Ok(())
+        }
+        None => {
+            let mut buf = [0u8; 8192];
+            loop {
+                let read = reader.read(&mut buf)?;
+                write!(stream, "{:x}\r\n", read)?;
+                stream.write_all(&buf[..read])?;
+                stream.write_all(b"\r\n")?;
+                if read == 0 {
+                    return Ok(());
+                }
+            }
+        }
+    }
 }
 
-pub fn receive_response(stream: &mut dyn Read) -> Result<Response, NanoGetError> {
-    let response_vec = read_response(stream).unwrap();
-    let response_str = String::from_utf8_lossy(&response_vec);
-    let response = parse_body_from_response(&response_str);
-    Ok(response)
+/// A stream that fails reads with an error of kind `BodyTooLarge` once more than `remaining` bytes
+/// would be read from it (see `Request::max_response_bytes`). Writes are passed through.
+struct LimitedStream<S> {
+    stream: S,
+    remaining: Option<u64>,
+}
+
+impl<S: Read> Read for LimitedStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = match self.remaining {
+            Some(remaining) => remaining,
+            None => return self.stream.read(buf),
+        };
+        if remaining == 0 {
+            // the limit is only exceeded if there is more to read.
+            return match self.stream.read(&mut [0u8])? {
+                0 => Ok(0),
+                _ => Err(io::Error::other(NanoGetError::new(ErrorKind::BodyTooLarge))),
+            };
+        }
+        let len = usize::try_from(remaining).map_or(buf.len(), |remaining| remaining.min(buf.len()));
+        let read = self.stream.read(&mut buf[..len])?;
+        self.remaining = Some(remaining - read as u64);
+        Ok(read)
+    }
+}
+
+impl<S: Write> Write for LimitedStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// A stream that is on a TCP socket, whose timeouts can be changed.
+pub(crate) trait HasSocket {
+    fn socket(&self) -> &TcpStream;
+}
+
+impl HasSocket for TcpStream {
+    fn socket(&self) -> &TcpStream {
+        self
+    }
+}
+
+/// Wraps the stream to renew its timeouts before every read and write (see `TimedStream`).
+pub(crate) fn timed<S: HasSocket>(stream: S, request: &Request, deadline: Option<Instant>) -> Result<TimedStream<S>, NanoGetError> {
+    let socket = stream.socket().try_clone().map_err(io_error)?;
+    Ok(TimedStream { stream, socket, timeout: request.timeouts.read, deadline })
+}
+
+/// A stream whose socket timeouts are renewed before every read and write, to the read timeout of
+/// the request or the time left until the deadline, whichever is shorter. This keeps a single
+/// blocking read from running past the deadline. Without a deadline, the timeouts set when
+/// connecting are left as they are.
+pub(crate) struct TimedStream<S> {
+    stream: S,
+    socket: TcpStream,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+}
+
+impl<S> TimedStream<S> {
+    fn renew_timeouts(&self) -> io::Result<()> {
+        if self.deadline.is_some() {
+            let timeout = remaining(self.timeout, self.deadline)?;
+            self.socket.set_read_timeout(timeout)?;
+            self.socket.set_write_timeout(timeout)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: Read> Read for TimedStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.renew_timeouts()?;
+        self.stream.read(buf)
+    }
+}
+
+impl<S: Write> Write for TimedStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.renew_timeouts()?;
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.renew_timeouts()?;
+        self.stream.flush()
+    }
+}
+
+/// A stream that keeps a copy of all the bytes written to and read from it (see
+/// `Request::capture_exchange`).
+struct CapturingStream<S> {
+    stream: S,
+    exchange: RawExchange,
+}
+
+impl<S: Read> Read for CapturingStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.stream.read(buf)?;
+        self.exchange.response.extend_from_slice(&buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<S: Write> Write for CapturingStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.stream.write(buf)?;
+        self.exchange.request.extend_from_slice(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// The head of a response, whose body was read into a separate buffer.
+struct ReceivedHead {
+    head: Vec<u8>,
+    trailers: Option<Vec<u8>>,
+    bytes_received: u64,
+    /// The heads of the interim responses before it, if the request collects them.
+    interim: Vec<Vec<u8>>,
+}
+
+/// Where the body of a response is read into: a buffer in memory, or a writer it is copied to.
+enum BodySink<'a> {
+    Buffer(&'a mut Vec<u8>),
+    Writer(&'a mut dyn Write),
+}
+
+impl BodySink<'_> {
+    /// Checks that a body of the given length fits in the buffer, failing with an error of kind
+    /// `BodyTooLarge` if it doesn't. Any length can be copied to a writer.
+    fn check_fits(&self, length: u64) -> Result<(), NanoGetError> {
+        match self {
+            BodySink::Buffer(buffer) if length > (isize::MAX as usize - buffer.len()) as u64 => {
+                Err(NanoGetError::new(ErrorKind::BodyTooLarge))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), NanoGetError> {
+        self.check_fits(data.len() as u64)?;
+        match self {
+            BodySink::Buffer(buffer) => {
+                buffer.extend_from_slice(data);
+                Ok(())
+            }
+            BodySink::Writer(writer) => writer.write_all(data).map_err(io_error),
+        }
+    }
+}
+
+/// Reads the body of the response with the given head (which was already read off the stream)
+/// into `body`.
+///
+/// If the length of the body is declared by the response, only that many bytes are read off the
+/// stream, so anything after it (like a pipelined response) is left unread. Otherwise, the body
+/// extends until the server closes the connection.
+///
+/// A chunked body is decoded as it is read, and the trailer headers after its last chunk are
+/// available from `Response::trailers`.
+///
+/// A response without a declared length on a connection that both sides keep alive can never end,
+/// as the server won't close the connection. Instead of waiting forever, this fails with an
+/// error of kind `ParseError`. This can only happen if the request replaces the default
+/// `connection: close` header with one asking for keep-alive. Without any `Connection` header (see
+/// `Request::remove_header`), the body is read until the server closes the connection, which is
+/// what servers that send no framing do.
+fn receive_body(stream: &mut dyn BufRead, head: Vec<u8>, request: &Request, deadline: Option<Instant>,
+                body: &mut BodySink) -> Result<ReceivedHead, NanoGetError> {
+    let budget_deadline = request.get_body_read_budget().map(|budget| Instant::now() + budget);
+    let deadline = earliest(deadline, budget_deadline);
+    if is_chunked(&head) {
+        let mut trailers = Vec::new();
+        let read = read_chunked_body(stream, body, &mut trailers, request, deadline)?;
+        check_no_trailing_data(stream, &head, request)?;
+        let bytes_received = head.len() as u64 + read;
+        return Ok(ReceivedHead { bytes_received, head, trailers: Some(trailers), interim: Vec::new() });
+    }
+    let body_length = get_body_length(&head);
+    if body_length.is_none() && is_keep_alive(&head) && keeps_connection_alive(request) {
+        return Err(NanoGetError::new(ErrorKind::ParseError));
+    }
+    let read = read_body(stream, body, body_length, request, deadline)?;
+    if body_length.is_some() {
+        check_no_trailing_data(stream, &head, request)?;
+    }
+    Ok(ReceivedHead { bytes_received: head.len() as u64 + read, head, trailers: None, interim: Vec::new() })
+}
+
+/// In strict mode, checks that nothing follows the end of the body on a connection that is closed
+/// after the response, failing with an error of kind `ParseError` otherwise.
+///
+/// Such data is either a second response the request didn't ask for (as in response smuggling) or
+/// a server bug. This waits for the server to close the connection (or the read timeout to pass).
+/// The connection is only known to be closed if the response says so, or the request sent
+/// `Connection: close`. Otherwise it is persistent, so the server may keep it open (and the next
+/// response may legitimately follow), and it is not checked.
+fn check_no_trailing_data(stream: &mut dyn BufRead, head: &[u8], request: &Request) -> Result<(), NanoGetError> {
+    if !request.is_strict() || (is_keep_alive(head) && !asks_to_close(request)) {
+        return Ok(());
+    }
+    match stream.fill_buf() {
+        Ok(extra) if !extra.is_empty() => Err(NanoGetError::with_source(
+            ErrorKind::ParseError, format!("{} unexpected bytes after the body", extra.len()))),
+        _ => Ok(()),
+    }
+}
+
+fn build_response(received: ReceivedHead, body: Vec<u8>, request: &Request) -> Response {
+    let mut response = new_response_from_parts(&received.head, body, request.url.clone(), request.get_body_mode());
+    if let Some(trailers) = received.trailers {
+        response.set_chunked_parts(&trailers, usize::try_from(received.bytes_received).unwrap_or(usize::MAX));
+    }
+    if !received.interim.is_empty() {
+        response.set_informational(received.interim.iter().map(|head| InformationalResponse::from_head(head)).collect());
+    }
+    response
+}
+
+/// Checks if the request has a `Connection` header that does not ask the server to close the
+/// connection after the response.
+fn keeps_connection_alive(request: &Request) -> bool {
+    let mut connection = request.get_request_headers()
+        .filter(|(k, _)| k.eq_ignore_ascii_case("connection"))
+        .peekable();
+    connection.peek().is_some() && !connection.any(|(_, v)| v.split(',').any(|t| t.trim().eq_ignore_ascii_case("close")))
+}
+
+/// Checks if the request has a `Connection` header that asks the server to close the connection
+/// after the response.
+fn asks_to_close(request: &Request) -> bool {
+    request.get_request_headers()
+        .any(|(k, v)| k.eq_ignore_ascii_case("connection") && v.split(',').any(|t| t.trim().eq_ignore_ascii_case("close")))
+}
+
+/// Reads the head of the final response, skipping any interim (1xx) responses before it, like
+/// `100 Continue` or `103 Early Hints`. The heads of the interim responses are added to `interim`
+/// if the request collects them (see `Request::collect_informational`).
+fn read_final_head(stream: &mut dyn BufRead, request: &Request, deadline: Option<Instant>,
+                   interim: &mut Vec<Vec<u8>>) -> Result<Vec<u8>, NanoGetError> {
+    loop {
+        let head = read_response_head(stream, request, deadline)?;
+        if !is_interim(&head) {
+            return Ok(head);
+        }
+        if request.get_collect_informational() {
+            interim.push(head);
+        }
+    }
+}
+
+fn read_response_head(stream: &mut dyn BufRead, request: &Request,
+                      deadline: Option<Instant>) -> Result<Vec<u8>, NanoGetError> {
+    let mut head: Vec<u8> = Vec::with_capacity(2048);
+    read_head(stream, &mut head, request.get_max_headers(), deadline)?;
+    check_cancelled(request)?;
+    if request.is_strict() {
+        validate_head(&head)?;
+        Ok(head)
+    } else {
+        Ok(normalize_line_endings(head))
+    }
+}
+
+/// Replaces bare `\n` line endings in the head with `\r\n`, as sent by some simple servers, so
+/// that the head can be parsed like any other.
+fn normalize_line_endings(head: Vec<u8>) -> Vec<u8> {
+    if !has_bare_lf(&head) {
+        return head;
+    }
+    let mut normalized = Vec::with_capacity(head.len() + 16);
+    for (i, byte) in head.iter().enumerate() {
+        if *byte == b'\n' && (i == 0 || head[i - 1] != b'\r') {
+            normalized.push(b'\r');
+        }
+        normalized.push(*byte);
+    }
+    normalized
+}
+
+/// Checks if the head is of an interim response, which is followed by another response.
+/// `101 Switching Protocols` is final, as the connection stops speaking HTTP after it.
+fn is_interim(head: &[u8]) -> bool {
+    let status = parse_status(head);
+    status.0.is_informational() && status.0.get_code() != Some(101)
+}
+
+/// Waits for the server to accept the body of the request with a `100 Continue` response.
+///
+/// Returns `None` if the body is to be sent: after a `100 Continue`, or if the server does not
+/// answer within the read timeout of the request (as a server that ignores `Expect` never will).
+/// If the server answers with a final response instead, its head is returned, and the body must
+/// not be sent.
+fn await_continue(stream: &mut dyn BufRead, request: &Request, deadline: Option<Instant>,
+                  interim: &mut Vec<Vec<u8>>) -> Result<Option<Vec<u8>>, NanoGetError> {
+    loop {
+        if let Err(err) = stream.fill_buf() {
+            if matches!(err.kind(), io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock) {
+                return Ok(None);
+            }
+        }
+        let head = read_response_head(stream, request, deadline)?;
+        if !is_interim(&head) {
+            return Ok(Some(head));
+        }
+        let accepted = parse_status(&head).0.get_code() == Some(100);
+        if request.get_collect_informational() {
+            interim.push(head);
+        }
+        if accepted {
+            return Ok(None);
+        }
+    }
+}
+
+/// Reads the status line and headers, failing with an error of kind `ParseError` if there are more
+/// than `max_headers` headers. The head ends at the first empty line, whether it ends with `\r\n`
+/// or a bare `\n`.
+fn read_head(reader: &mut dyn BufRead, lines: &mut Vec<u8>, max_headers: usize,
+             deadline: Option<Instant>) -> Result<(), NanoGetError> {
+    let mut count = 0;
+    loop {
+        let read = reader.read_until(b'\n', lines).map_err(io_error)?;
+        if read == 0 || lines.ends_with(b"\n\r\n") || lines.ends_with(b"\n\n") {
+            return Ok(());
+        }
+        count += 1;
+        if count > max_headers.saturating_add(1) {
+            return Err(NanoGetError::new(ErrorKind::ParseError));
+        }
+        check_deadline(deadline)?;
+    }
 }
 
-fn read_response(stream: &mut dyn Read) -> std::io::Result<Vec<u8>> {
-    let mut lines: Vec<u8> = Vec::with_capacity(2048);
-    stream.read_to_end(&mut lines)?;
-    Ok(lines)
+/// Reads the body of the given length, or until the end of the stream if the length isn't known.
+///
+/// Returns the number of bytes read, which is less than the length if the stream ended early.
+/// The deadline is checked between reads, so a single blocking read is not interrupted by it.
+///
+/// Fails with an error of kind `BodyTooLarge` if the body is read into a buffer that can't hold it.
+fn read_body(reader: &mut dyn Read, body: &mut BodySink, length: Option<u64>, request: &Request,
+             deadline: Option<Instant>) -> Result<u64, NanoGetError> {
+    if let Some(length) = length {
+        body.check_fits(length)?;
+    }
+    let rate = request.get_max_download_rate();
+    let started = Instant::now();
+    let mut remaining = length.unwrap_or(u64::MAX);
+    let mut total = 0;
+    let mut buf = [0u8; 8192];
+    while remaining > 0 {
+        let to_read = usize::try_from(remaining.min(rate.unwrap_or(u64::MAX)))
+            .map_or(buf.len(), |remaining| remaining.min(buf.len()));
+        let read = reader.read(&mut buf[..to_read]).map_err(io_error)?;
+        if read == 0 {
+            break;
+        }
+        remaining -= read as u64;
+        total += read as u64;
+        check_cancelled(request)?;
+        body.write(&buf[..read])?;
+        if let Some(rate) = rate {
+            pace(started, total, rate, deadline);
+        }
+        check_deadline(deadline)?;
+    }
+    Ok(total)
+}
+
+/// Sleeps until `total` bytes have taken at least as long as they should at `rate` bytes per second
+/// since `started` (see `Request::max_download_rate`), or until the deadline, if it is sooner.
+fn pace(started: Instant, total: u64, rate: u64, deadline: Option<Instant>) {
+    let due = started + Duration::from_secs_f64(total as f64 / rate as f64);
+    let until = deadline.map_or(due, |deadline| due.min(deadline));
+    let now = Instant::now();
+    if until > now {
+        thread::sleep(until - now);
+    }
+}
+
+/// Decodes a chunked body into `body`, and copies the trailer section after the last chunk into
+/// `trailers`.
+///
+/// Returns the number of bytes read off the stream. Chunk extensions are ignored, and a stream that
+/// ends early is treated as the end of the body.
+fn read_chunked_body(reader: &mut dyn BufRead, body: &mut BodySink, trailers: &mut Vec<u8>,
+                     request: &Request, deadline: Option<Instant>) -> Result<u64, NanoGetError> {
+    let mut total = 0;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        total += reader.read_until(b'\n', &mut line).map_err(io_error)? as u64;
+        if line.is_empty() {
+            return Ok(total);
+        }
+        let size = parse_chunk_size(&line)?;
+        if size == 0 {
+            break;
+        }
+        let read = read_body(reader, body, Some(size), request, deadline)?;
+        total += read;
+        if read < size {
+            return Ok(total);
+        }
+        line.clear();
+        total += reader.read_until(b'\n', &mut line).map_err(io_error)? as u64;
+        if !line.iter().all(|b| *b == b'\r' || *b == b'\n') {
+            return Err(NanoGetError::new(ErrorKind::ParseError));
+        }
+    }
+    let mut count = 0;
+    loop {
+        let read = reader.read_until(b'\n', trailers).map_err(io_error)?;
+        total += read as u64;
+        if read == 0 || trailers.ends_with(b"\n\r\n") || trailers == b"\r\n" {
+            return Ok(total);
+        }
+        count += 1;
+        if count > request.get_max_headers() {
+            return Err(NanoGetError::new(ErrorKind::ParseError));
+        }
+        check_deadline(deadline)?;
+    }
+}
+
+pub fn parse_chunk_size(line: &[u8]) -> Result<u64, NanoGetError> {
+    let line = std::str::from_utf8(line).map_err(|_err| NanoGetError::new(ErrorKind::ParseError))?;
+    let size = line.split(';').next().unwrap_or_default().trim();
+    u64::from_str_radix(size, 16).map_err(|_err| NanoGetError::new(ErrorKind::ParseError))
+}
+
+fn earliest(first: Option<Instant>, second: Option<Instant>) -> Option<Instant> {
+    match (first, second) {
+        (Some(first), Some(second)) => Some(first.min(second)),
+        (first, second) => first.or(second),
+    }
+}
+
+fn check_deadline(deadline: Option<Instant>) -> Result<(), NanoGetError> {
+    if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+        Err(NanoGetError::new(ErrorKind::Timeout))
+    } else {
+        Ok(())
+    }
+}
+
+/// Maps an io error to a `Timeout` error if it was caused by a timeout, else a `NetworkError`.
+///
+/// The io error is kept as the source of the error, unless it wraps a `NanoGetError` (raised while
+/// reading, like by `LimitedStream`), which is returned as-is.
+pub fn io_error(err: io::Error) -> NanoGetError {
+    if err.get_ref().is_some_and(|inner| inner.is::<NanoGetError>()) {
+        return *err.into_inner().unwrap().downcast::<NanoGetError>().unwrap();
+    }
+    match err.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => NanoGetError::with_source(ErrorKind::Timeout, err),
+        _ => NanoGetError::with_source(ErrorKind::NetworkError, err),
+    }
+}
+
+/// Logs the request line (at debug level) and headers (at trace level), with credentials redacted.
+#[cfg(feature = "log")]
+fn log_request(request: &Request, headers: &[(String, String)]) {
+    log::debug!("{} {}", request.get_request_type(), request.url.get_full_url());
+    for (k, v) in headers {
+        log::trace!("> {}: {}", k, redact_header_value(k, v));
+    }
+}
+
+#[cfg(not(feature = "log"))]
+fn log_request(_request: &Request, _headers: &[(String, String)]) {}
+
+/// Logs the response status and how long the exchange took (at debug level).
+#[cfg(feature = "log")]
+fn log_response(received: &ReceivedHead, started: Instant) {
+    let head = String::from_utf8_lossy(&received.head);
+    let mut lines = head.lines();
+    log::debug!("{} ({} bytes in {:?})", lines.next().unwrap_or_default(), received.bytes_received, started.elapsed());
+    for (k, v) in lines.filter_map(|line| line.split_once(':')) {
+        log::trace!("< {}: {}", k, redact_header_value(k, v.trim()));
+    }
 }
 
-fn parse_body_from_response(response: &str) -> Response {
-    new_response_from_complete(response.to_string())
+#[cfg(not(feature = "log"))]
+fn log_response(_received: &ReceivedHead, _started: Instant) {}
+
+fn check_cancelled(request: &Request) -> Result<(), NanoGetError> {
+    if request.is_cancelled() {
+        Err(NanoGetError::new(ErrorKind::Cancelled))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::BodyMode;
+    use crate::testing::{serve, serve_with};
+
+    struct ThrottledReader<'a> {
+        data: &'a [u8],
+        delay: Duration,
+    }
+
+    impl Read for ThrottledReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            thread::sleep(self.delay);
+            let n = self.data.len().min(buf.len()).min(1);
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    /// Records each write separately.
+    #[derive(Default)]
+    struct WriteRecorder {
+        writes: Vec<Vec<u8>>,
+    }
+
+    impl Write for WriteRecorder {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.writes.push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn receive_response(stream: &mut dyn BufRead, request: &Request, deadline: Option<Instant>) -> Result<Response, NanoGetError> {
+        let mut body = Vec::new();
+        let mut interim = Vec::new();
+        let head = read_final_head(stream, request, deadline, &mut interim)?;
+        let mut received = receive_body(stream, head, request, deadline, &mut BodySink::Buffer(&mut body))?;
+        received.interim = interim;
+        Ok(build_response(received, body, request))
+    }
+
+    fn test_request() -> Request {
+        Request::default_get_request("http://example.com/").unwrap()
+    }
+
+    fn unused_port() -> u16 {
+        TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    #[test]
+    fn test_resolve_failure_is_a_dns_error() {
+        let err = resolve(&Url::new("http://nonexistent.invalid/")).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::DnsError);
+    }
+
+    #[test]
+    fn test_connect_refused_without_retries() {
+        let request = Request::default_get_request(format!("http://127.0.0.1:{}/", unused_port())).unwrap();
+        let err = connect(&request, None).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::NetworkError);
+        let source = std::error::Error::source(&err).unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.kind(), io::ErrorKind::ConnectionRefused);
+    }
+
+    #[test]
+    fn test_connect_timeout_is_shared_by_all_addresses() {
+        // addresses in the IPv6 discard prefix, which either never answer or are unreachable.
+        let addrs: Vec<SocketAddr> = (1..=4).map(|i| format!("[100::{}]:80", i).parse().unwrap()).collect();
+        let mut request = Request::default_get_request("http://example.com/").unwrap();
+        request.connect_timeout(Duration::from_millis(200));
+        let started = Instant::now();
+        let err = connect_tcp(&addrs, &request, None).unwrap_err();
+        assert!(started.elapsed() < Duration::from_millis(600));
+        if err.kind() == &ErrorKind::Timeout {
+            assert!(std::error::Error::source(&err).unwrap().to_string().ends_with("of 4 addresses"));
+        }
+        let err = connect_any(&addrs, Some(Instant::now()), &SocketConfig::default()).unwrap_err();
+        assert_eq!(err.to_string(), "connect timed out after trying 0 of 4 addresses");
+    }
+
+    #[test]
+    fn test_is_private_address() {
+        for ip in &["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "0.0.0.0", "::1",
+            "fd00::1", "fe80::1", "::ffff:127.0.0.1", "::ffff:10.0.0.1"] {
+            assert!(is_private_address(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in &["93.184.216.34", "8.8.8.8", "172.32.0.1", "2606:2800:220:1::1", "::ffff:8.8.8.8"] {
+            assert!(!is_private_address(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn test_connect_blocks_private_addresses() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/", port)).unwrap();
+        request.block_private_addresses(true);
+        let err = connect(&request, None).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::BlockedAddress);
+        request.block_private_addresses(false);
+        assert!(connect(&request, None).is_ok());
+    }
+
+    #[test]
+    fn test_connect_tunnel() {
+        let (port, proxy) = serve(vec![b"HTTP/1.1 200 Connection established\r\n\r\nSSH-2.0-server\r\n"]);
+        let mut stream = connect_tunnel(&Url::from_host_port("127.0.0.1", port), "git.example.com:22").unwrap();
+        let mut greeting = String::new();
+        stream.read_to_string(&mut greeting).unwrap();
+        assert_eq!(greeting, "SSH-2.0-server\r\n");
+        assert!(proxy.join().unwrap()[0].starts_with("CONNECT git.example.com:22 HTTP/1.1\r\nHost: git.example.com:22\r\n"));
+    }
+
+    #[test]
+    fn test_connect_tunnel_refused_by_proxy() {
+        let (port, proxy) = serve(vec![b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n"]);
+        let err = connect_tunnel(&Url::from_host_port("127.0.0.1", port), "example.com:443").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ProxyError);
+        assert!(std::error::Error::source(&err).unwrap().to_string().contains("407 Proxy Authentication Required"));
+        proxy.join().unwrap();
+        let err = connect_tunnel(&Url::from_host_port("127.0.0.1", 1), "example.com:443\r\nX: y").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ParseError);
+    }
+
+    #[test]
+    fn test_connect_retries_until_server_is_up() {
+        let port = unused_port();
+        let server = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+            listener.accept().unwrap();
+        });
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/", port)).unwrap();
+        request.connect_retries(50, Duration::from_millis(20));
+        assert!(connect(&request, None).is_ok());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_receive_response_byte_counts() {
+        let raw = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\nhello, wörld";
+        let response = receive_response(&mut raw.as_bytes(), &test_request(), None).unwrap();
+        assert_eq!(response.body_len(), "hello, wörld".len());
+        assert_eq!(response.bytes_received(), raw.len());
+    }
+
+    #[test]
+    fn test_receive_response_binary_body() {
+        let mut raw = b"HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\n\r\n".to_vec();
+        raw.extend_from_slice(&[0x00, 0xff, 0xfe, 0x10]);
+        let response = receive_response(&mut raw.as_slice(), &test_request(), None).unwrap();
+        assert_eq!(response.body_bytes(), &[0x00, 0xff, 0xfe, 0x10]);
+        assert_eq!(response.body, "");
+        assert_eq!(response.text().unwrap_err().kind(), &ErrorKind::ParseError);
+        let mut request = test_request();
+        request.body_mode(BodyMode::Text);
+        let response = receive_response(&mut raw.as_slice(), &request, None).unwrap();
+        assert_eq!(response.body, "\u{0}\u{fffd}\u{fffd}\u{10}");
+    }
+
+    #[test]
+    fn test_body_mode_by_content_type() {
+        let body_of = |content_type: &str, mode: BodyMode| {
+            let raw = format!("HTTP/1.1 200 OK\r\nContent-Type: {}\r\n\r\nabc", content_type);
+            let mut request = test_request();
+            request.body_mode(mode);
+            receive_response(&mut raw.as_bytes(), &request, None).unwrap().body
+        };
+        for text in &["text/plain; charset=utf-8", "Application/JSON", "application/problem+json", "image/svg+xml"] {
+            assert_eq!(body_of(text, BodyMode::Auto), "abc", "{}", text);
+        }
+        assert_eq!(body_of("image/png", BodyMode::Auto), "");
+        assert_eq!(body_of("text/plain", BodyMode::Bytes), "");
+        let response = receive_response(&mut "HTTP/1.1 200 OK\r\n\r\nabc".as_bytes(), &test_request(), None).unwrap();
+        assert_eq!(response.body, "abc");
+    }
+
+    #[test]
+    fn test_body_read_budget_exceeded() {
+        let raw = format!("HTTP/1.1 200 OK\r\n\r\n{}", "a".repeat(100));
+        let mut stream = BufReader::new(ThrottledReader { data: raw.as_bytes(), delay: Duration::from_millis(5) });
+        let mut request = test_request();
+        request.body_read_budget(Duration::from_millis(50));
+        let err = receive_response(&mut stream, &request, None).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Timeout);
+    }
+
+    #[test]
+    fn test_body_read_budget_not_exceeded() {
+        let raw = "HTTP/1.1 200 OK\r\n\r\nabc";
+        let mut stream = BufReader::new(ThrottledReader { data: raw.as_bytes(), delay: Duration::from_millis(1) });
+        let mut request = test_request();
+        request.body_read_budget(Duration::from_secs(5));
+        let response = receive_response(&mut stream, &request, None).unwrap();
+        assert_eq!(response.body, "abc");
+    }
+
+    #[test]
+    fn test_cancelled_during_body() {
+        let raw = format!("HTTP/1.1 200 OK\r\n\r\n{}", "a".repeat(1000));
+        let mut stream = BufReader::new(ThrottledReader { data: raw.as_bytes(), delay: Duration::from_millis(1) });
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut request = test_request();
+        request.cancel_flag(Arc::clone(&cancel));
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            cancel.store(true, Ordering::SeqCst);
+        });
+        let err = receive_response(&mut stream, &request, None).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Cancelled);
+        canceller.join().unwrap();
+    }
+
+    #[test]
+    fn test_cancelled_before_send() {
+        let mut request = test_request();
+        request.cancel_flag(Arc::new(AtomicBool::new(true)));
+        let mut sent = Vec::new();
+        let mut stream = io::Cursor::new(&mut sent);
+        let err = execute(&mut stream, &request, None).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Cancelled);
+        assert!(sent.is_empty());
+    }
+
+    #[test]
+    fn test_send_body_from_reader_with_length() {
+        let mut request = test_request();
+        request.body_from_reader(io::Cursor::new(b"hello world".to_vec()), Some(5));
+        let mut sent = Vec::new();
+        send_request(&mut sent, &request).unwrap();
+        let sent = String::from_utf8(sent).unwrap();
+        assert!(sent.contains("\r\ncontent-length: 5\r\n"));
+        assert!(sent.ends_with("\r\n\r\nhello"));
+    }
+
+    #[test]
+    fn test_send_body_from_reader_chunked() {
+        let mut request = test_request();
+        request.body_from_reader(io::Cursor::new(b"hello world".to_vec()), None);
+        let mut sent = Vec::new();
+        send_request(&mut sent, &request).unwrap();
+        let sent = String::from_utf8(sent).unwrap();
+        assert!(sent.contains("\r\ntransfer-encoding: chunked\r\n"));
+        assert!(sent.ends_with("\r\n\r\nb\r\nhello world\r\n0\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_send_body_from_short_reader() {
+        let mut request = test_request();
+        request.body_from_reader(io::Cursor::new(b"abc".to_vec()), Some(10));
+        let err = send_request(&mut Vec::new(), &request).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_send_body_slice() {
+        let mut request = test_request();
+        request.body = Some("ignored".to_string());
+        request.set_body_slice(&[0xff, 0x00, 0xfe]);
+        let mut sent = Vec::new();
+        send_request(&mut sent, &request).unwrap();
+        assert!(String::from_utf8_lossy(&sent).contains("\r\ncontent-length: 3\r\n"));
+        assert!(sent.ends_with(b"\r\n\r\n\xff\x00\xfe"));
+    }
+
+    #[test]
+    fn test_send_string_body_has_content_length() {
+        let mut request = test_request();
+        request.body = Some("héllo".to_string());
+        let mut sent = Vec::new();
+        send_request(&mut sent, &request).unwrap();
+        let sent = String::from_utf8(sent).unwrap();
+        assert!(sent.contains("\r\ncontent-length: 6\r\n"));
+        assert!(sent.ends_with("\r\n\r\nhéllo"));
+    }
+
+    #[test]
+    fn test_before_send_sees_final_headers() {
+        let mut request = test_request();
+        request.add_header("x-trace", "abc");
+        request.before_send(Box::new(|headers| {
+            let count = headers.len().to_string();
+            headers.remove("accept");
+            headers.insert("x-header-count".to_string(), count);
+        }));
+        let mut sent = Vec::new();
+        send_request(&mut sent, &request).unwrap();
+        let sent = String::from_utf8(sent).unwrap();
+        assert!(sent.contains("\r\nx-header-count: 5\r\n"));
+        assert!(sent.contains("\r\nx-trace: abc\r\n"));
+        assert!(!sent.contains("accept:"));
+        assert!(request.get_request_headers().any(|(k, _)| k == "accept"));
+    }
+
+    #[test]
+    fn test_send_empty_body_has_content_length() {
+        let mut request = test_request();
+        let mut sent = Vec::new();
+        send_request(&mut sent, &request).unwrap();
+        assert!(!String::from_utf8(sent).unwrap().contains("content-length"));
+        request.body = Some(String::new());
+        let mut sent = Vec::new();
+        send_request(&mut sent, &request).unwrap();
+        let sent = String::from_utf8(sent).unwrap();
+        assert!(sent.contains("\r\ncontent-length: 0\r\n"));
+        assert!(sent.ends_with("\r\n\r\n"));
+    }
+
+    /// Serves a single connection that reads the request head, optionally answers `100 Continue`,
+    /// and then reads the chunked body (unless `reject` is set) before answering. Returns the port
+    /// and a handle to the request head and body that were received.
+    fn serve_expect(answer_continue: bool, reject: bool) -> (u16, thread::JoinHandle<Vec<(String, String)>>) {
+        serve_with(1, move |mut stream, mut reader, head| {
+            let mut body = String::new();
+            if reject {
+                stream.write_all(b"HTTP/1.1 413 Payload Too Large\r\nContent-Length: 0\r\n\r\n").unwrap();
+                stream.shutdown(std::net::Shutdown::Write).unwrap();
+                reader.read_to_string(&mut body).unwrap();
+                return (head, body);
+            }
+            if answer_continue {
+                stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").unwrap();
+            }
+            while reader.read_line(&mut body).unwrap() > 0 && !body.ends_with("0\r\n\r\n") {}
+            stream.write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n").unwrap();
+            (head, body)
+        })
+    }
+
+    fn upload_request(port: u16) -> Request {
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/upload", port)).unwrap();
+        request.body_from_reader(io::Cursor::new(b"hello".to_vec()), None);
+        request.expect_continue(true);
+        request
+    }
+
+    #[test]
+    fn test_expect_continue_sends_body_after_continue() {
+        let (port, server) = serve_expect(true, false);
+        let response = upload_request(port).execute().unwrap();
+        assert_eq!(response.get_status_code(), Some(201));
+        let (head, body) = server.join().unwrap().remove(0);
+        assert!(head.contains("\r\nexpect: 100-continue\r\n"));
+        assert!(head.contains("\r\ntransfer-encoding: chunked\r\n"));
+        assert_eq!(body, "5\r\nhello\r\n0\r\n\r\n");
+    }
+
+    #[test]
+    fn test_expect_continue_skips_body_on_final_response() {
+        let (port, server) = serve_expect(false, true);
+        let response = upload_request(port).execute().unwrap();
+        assert_eq!(response.get_status_code(), Some(413));
+        let (_, body) = server.join().unwrap().remove(0);
+        assert_eq!(body, "");
+    }
+
+    #[test]
+    fn test_expect_continue_sends_body_after_read_timeout() {
+        let (port, server) = serve_expect(false, false);
+        let mut request = upload_request(port);
+        request.read_timeout(Duration::from_millis(200));
+        let response = request.execute().unwrap();
+        assert_eq!(response.get_status_code(), Some(201));
+        assert_eq!(server.join().unwrap()[0].1, "5\r\nhello\r\n0\r\n\r\n");
+    }
+
+    #[test]
+    fn test_receive_skips_interim_responses() {
+        let raw = "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 103 Early Hints\r\nLink: </a.css>\r\n\r\n\
+                   HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+        let response = receive_response(&mut raw.as_bytes(), &test_request(), None).unwrap();
+        assert_eq!(response.get_status_code(), Some(200));
+        assert_eq!(response.body, "ok");
+    }
+
+    #[test]
+    fn test_receive_chunked_body_one_byte_at_a_time() {
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n1a\r\nabcdefghijklmnopqrstuvwxyz\r\n\
+                    3;ext=1\r\n123\r\n0\r\nX-Trailer: 1\r\n\r\n";
+        let reader = ThrottledReader { data: raw, delay: Duration::from_millis(0) };
+        let response = receive_response(&mut BufReader::with_capacity(1, reader), &test_request(), None).unwrap();
+        assert_eq!(response.body, "abcdefghijklmnopqrstuvwxyz123");
+        assert_eq!(response.trailers().unwrap().collect::<Vec<_>>(), vec![("X-Trailer", "1")]);
+    }
+
+    #[test]
+    fn test_receive_collects_interim_responses() {
+        let raw = "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 103 Early Hints\r\nLink: </a.css>; rel=preload\r\n\
+                   Link: </b.js>; rel=preload\r\n\r\nHTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+        let mut request = test_request();
+        assert!(receive_response(&mut raw.as_bytes(), &request, None).unwrap().informational().is_empty());
+        request.collect_informational(true);
+        let response = receive_response(&mut raw.as_bytes(), &request, None).unwrap();
+        assert_eq!(response.body, "ok");
+        let codes: Vec<Option<u16>> = response.informational().iter().map(|r| r.status.0.get_code()).collect();
+        assert_eq!(codes, vec![Some(100), Some(103)]);
+        assert_eq!(response.informational()[1].headers, vec![
+            ("Link".to_string(), "</a.css>; rel=preload".to_string()),
+            ("Link".to_string(), "</b.js>; rel=preload".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_send_request_in_a_single_write() {
+        let mut request = test_request();
+        request.body = Some("hello".to_string());
+        let mut recorder = WriteRecorder::default();
+        send_request(&mut recorder, &request).unwrap();
+        assert_eq!(recorder.writes.len(), 1);
+        assert!(recorder.writes[0].ends_with(b"\r\n\r\nhello"));
+        request.coalesce_writes(false);
+        let mut recorder = WriteRecorder::default();
+        send_request(&mut recorder, &request).unwrap();
+        assert_eq!(recorder.writes.len(), 2);
+        assert_eq!(recorder.writes[1], b"hello");
+    }
+
+    #[test]
+    fn test_send_many_headers_through_the_write_buffer() {
+        let mut request = test_request();
+        for i in 0..50 {
+            request.add_header(&format!("x-trace-{}", i), "0123456789abcdef");
+        }
+        let mut recorder = WriteRecorder::default();
+        send_request(&mut recorder, &request).unwrap();
+        assert_eq!(recorder.writes.len(), 1);
+        let head = recorder.writes.concat();
+        request.write_buffer_size(256);
+        let mut recorder = WriteRecorder::default();
+        send_request(&mut recorder, &request).unwrap();
+        assert!(recorder.writes.len() > 1 && recorder.writes.len() < 50);
+        assert_eq!(recorder.writes.concat(), head);
+    }
+
+    #[test]
+    fn test_send_chunked_body_through_the_write_buffer() {
+        let mut request = test_request();
+        request.body_from_reader(io::Cursor::new(b"hello world".to_vec()), None);
+        let mut recorder = WriteRecorder::default();
+        send_request(&mut recorder, &request).unwrap();
+        assert_eq!(recorder.writes.len(), 1);
+        assert!(recorder.writes[0].ends_with(b"\r\n\r\nb\r\nhello world\r\n0\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_keep_alive_without_length_is_an_error() {
+        let mut request = test_request();
+        request.add_header("connection", "keep-alive");
+        let raw = "HTTP/1.1 200 OK\r\nConnection: keep-alive\r\n\r\nbody";
+        let err = receive_response(&mut raw.as_bytes(), &request, None).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ParseError);
+        let raw = "HTTP/1.0 200 OK\r\n\r\nbody";
+        assert_eq!(receive_response(&mut raw.as_bytes(), &request, None).unwrap().body, "body");
+        let raw = "HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nbody";
+        assert_eq!(receive_response(&mut raw.as_bytes(), &request, None).unwrap().body, "body");
+    }
+
+    #[test]
+    fn test_keep_alive_without_length_reads_until_close_when_closing() {
+        let raw = "HTTP/1.1 200 OK\r\nConnection: keep-alive\r\n\r\nbody";
+        let response = receive_response(&mut raw.as_bytes(), &test_request(), None).unwrap();
+        assert_eq!(response.body, "body");
+    }
+
+    #[test]
+    fn test_without_connection_header() {
+        let mut request = test_request();
+        request.remove_header("Connection");
+        let mut sent = Vec::new();
+        send_request(&mut sent, &request).unwrap();
+        assert!(!String::from_utf8(sent).unwrap().to_ascii_lowercase().contains("connection"));
+        let raw = "HTTP/1.1 200 OK\r\n\r\nbody";
+        assert_eq!(receive_response(&mut raw.as_bytes(), &request, None).unwrap().body, "body");
+    }
+
+    #[test]
+    fn test_receive_response_status_line() {
+        let raw = "HTTP/1.0 404 Not Found\r\nContent-Length: 0\r\n\r\n";
+        let response = receive_response(&mut raw.as_bytes(), &test_request(), None).unwrap();
+        assert_eq!(response.http_version(), "HTTP/1.0");
+        assert_eq!(response.status_line(), "HTTP/1.0 404 Not Found");
+        assert_eq!(response.status.1.as_deref(), Some("Not Found"));
+    }
+
+    #[test]
+    fn test_receive_chunked_response_with_trailers() {
+        let next = "HTTP/1.1 204 No Content\r\n\r\n";
+        let raw = format!("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nTrailer: Grpc-Status\r\n\r\n\
+                           5;ext=1\r\nhello\r\n7\r\n, world\r\n0\r\nGrpc-Status: 0\r\n\r\n{}", next);
+        let mut stream = raw.as_bytes();
+        let response = receive_response(&mut stream, &test_request(), None).unwrap();
+        assert_eq!(response.body, "hello, world");
+        let trailers: Vec<(&str, &str)> = response.trailers().unwrap().collect();
+        assert_eq!(trailers, vec![("Grpc-Status", "0")]);
+        assert_eq!(response.bytes_received(), raw.len() - next.len());
+        assert_eq!(stream, next.as_bytes());
+    }
+
+    #[test]
+    fn test_receive_chunked_response_without_trailers() {
+        let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nContent-Length: 1\r\n\r\n3\r\nabc\r\n0\r\n\r\n";
+        let response = receive_response(&mut raw.as_bytes(), &test_request(), None).unwrap();
+        assert_eq!(response.body, "abc");
+        assert_eq!(response.trailers().unwrap().count(), 0);
+        let response = receive_response(&mut "HTTP/1.1 200 OK\r\n\r\nabc".as_bytes(), &test_request(), None).unwrap();
+        assert!(response.trailers().is_none());
+    }
+
+    #[test]
+    fn test_receive_chunked_response_with_bad_chunk_size() {
+        let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\nabc\r\n0\r\n\r\n";
+        let err = receive_response(&mut raw.as_bytes(), &test_request(), None).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ParseError);
+    }
+
+    #[test]
+    fn test_receive_body_too_large_for_buffer() {
+        let raw = "HTTP/1.1 200 OK\r\nContent-Length: 18446744073709551615\r\n\r\nabc";
+        let err = receive_response(&mut raw.as_bytes(), &test_request(), None).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::BodyTooLarge);
+        let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nffffffffffffffff\r\nabc";
+        let err = receive_response(&mut raw.as_bytes(), &test_request(), None).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::BodyTooLarge);
+    }
+
+    #[test]
+    fn test_execute_head_only() {
+        let (port, server) = serve(vec![b"HTTP/1.1 200 OK\r\nContent-Length: 1000000\r\nETag: \"v1\"\r\n\r\npartial"]);
+        let request = Request::default_get_request(format!("http://127.0.0.1:{}/big", port)).unwrap();
+        let response = request.execute_head_only().unwrap();
+        assert_eq!(response.get_status_code(), Some(200));
+        assert_eq!(response.header_values("etag"), vec!["\"v1\""]);
+        assert!(response.body_bytes().is_empty());
+        assert!(server.join().unwrap()[0].starts_with("GET /big HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn test_max_download_rate() {
+        let raw = format!("HTTP/1.1 200 OK\r\nContent-Length: 300\r\n\r\n{}", "a".repeat(300));
+        let mut request = test_request();
+        let started = Instant::now();
+        receive_response(&mut raw.as_bytes(), &request, None).unwrap();
+        assert!(started.elapsed() < Duration::from_millis(200));
+
+        request.max_download_rate(1000);
+        let started = Instant::now();
+        assert_eq!(receive_response(&mut raw.as_bytes(), &request, None).unwrap().body_len(), 300);
+        assert!(started.elapsed() >= Duration::from_millis(300));
+
+        let raw = format!("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n96\r\n{0}\r\n96\r\n{0}\r\n0\r\n\r\n",
+                          "a".repeat(150));
+        let started = Instant::now();
+        assert_eq!(receive_response(&mut raw.as_bytes(), &request, None).unwrap().body_len(), 300);
+        assert!(started.elapsed() >= Duration::from_millis(300));
+
+        // the pacing stops at the deadline, which then fails the request.
+        let deadline = Some(Instant::now() + Duration::from_millis(100));
+        let err = receive_response(&mut raw.as_bytes(), &request, deadline).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Timeout);
+    }
+
+    #[test]
+    fn test_max_response_bytes() {
+        const RAW: &[u8] = b"HTTP/1.1 200 OK\r\nX-Padding: 0123456789abcdef0123456789abcdef\r\nContent-Length: 2\r\n\r\nok";
+        let (port, server) = serve(vec![RAW]);
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/", port)).unwrap();
+        request.max_response_bytes(RAW.len() as u64);
+        assert_eq!(request.execute().unwrap().body, "ok");
+        server.join().unwrap();
+        // the headers alone are larger than the cap.
+        request.max_response_bytes(40);
+        let (port, server) = serve(vec![RAW]);
+        request.url = Url::from_host_port("127.0.0.1", port);
+        assert_eq!(request.execute().unwrap_err().kind(), &ErrorKind::BodyTooLarge);
+        server.join().unwrap();
+        let (port, server) = serve(vec![RAW]);
+        request.url = Url::from_host_port("127.0.0.1", port);
+        assert_eq!(request.execute_lines().err().unwrap().kind(), &ErrorKind::BodyTooLarge);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_execute_to_writer_streams_any_length() {
+        let (port, server) = serve(vec![b"HTTP/1.1 200 OK\r\nContent-Length: 18446744073709551615\r\n\r\nabc"]);
+        let request = Request::default_get_request(format!("http://127.0.0.1:{}/", port)).unwrap();
+        let mut written = Vec::new();
+        let status = request.execute_to_writer(&mut written).unwrap();
+        assert_eq!(status.0.get_code(), Some(200));
+        assert_eq!(written, b"abc");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_data_after_the_body() {
+        let mut request = test_request();
+        let raw = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nokHTTP/1.1 200 OK\r\n\r\n";
+        assert_eq!(receive_response(&mut raw.as_bytes(), &request, None).unwrap().body, "ok");
+        request.strict(true);
+        let err = receive_response(&mut raw.as_bytes(), &request, None).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ParseError);
+        let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nok\r\n0\r\n\r\ngarbage";
+        let err = receive_response(&mut raw.as_bytes(), &request, None).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ParseError);
+        let raw = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+        assert_eq!(receive_response(&mut raw.as_bytes(), &request, None).unwrap().body, "ok");
+        request.add_header("connection", "keep-alive");
+        let raw = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nokHTTP/1.1 200 OK\r\n\r\n";
+        assert_eq!(receive_response(&mut raw.as_bytes(), &request, None).unwrap().body, "ok");
+    }
+
+    #[test]
+    fn test_strict_mode_does_not_wait_on_a_persistent_connection() {
+        let (port, server) = serve_with(1, |mut stream, mut reader, _| {
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok").unwrap();
+            // keeps the connection open until the client closes it.
+            reader.read_to_end(&mut Vec::new()).unwrap();
+        });
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/", port)).unwrap();
+        request.remove_header("Connection");
+        request.strict(true);
+        request.read_timeout(Duration::from_secs(3));
+        let started = Instant::now();
+        assert_eq!(request.execute().unwrap().body, "ok");
+        assert!(started.elapsed() < Duration::from_secs(2));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_bare_lf_line_endings() {
+        let mut request = test_request();
+        for raw in &["HTTP/1.1 200 OK\nContent-Type: text/plain\nContent-Length: 5\n\nhello\nworld",
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\nContent-Length: 5\r\n\nhello\nworld"] {
+            let response = receive_response(&mut raw.as_bytes(), &request, None).unwrap();
+            assert_eq!(response.body, "hello");
+            assert_eq!(response.header_values("content-type"), vec!["text/plain"]);
+        }
+        let raw = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello\nworld";
+        assert_eq!(receive_response(&mut raw.as_bytes(), &request, None).unwrap().body, "hello");
+
+        request.strict(true);
+        let raw = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello";
+        assert_eq!(receive_response(&mut raw.as_bytes(), &request, None).unwrap().body, "hello");
+        for raw in &["HTTP/1.1 200 OK\nContent-Length: 5\n\nhello", "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\nhello",
+            "HTTP/1.1 200 OK\r\nX-A: 1\nContent-Length: 5\r\n\r\nhello"] {
+            let err = receive_response(&mut raw.as_bytes(), &request, None).unwrap_err();
+            assert_eq!(err.kind(), &ErrorKind::ParseError, "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_malformed_responses() {
+        let mut strict = test_request();
+        strict.strict(true);
+        let malformed = vec![
+            b"HTTP/1.1 2000 OK\r\n\r\n".to_vec(),
+            b"HTTP/1.1 OK\r\n\r\n".to_vec(),
+            b"HTTP/1.1 200 OK\r\nno colon here\r\n\r\n".to_vec(),
+            b"HTTP/1.1 200 OK\r\nX-Name: \xff\r\n\r\n".to_vec(),
+            b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec(),
+            b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\nContent-Length: 4\r\n\r\nabcd".to_vec(),
+            b"HTTP/1.1 200 OK\r\nContent-Length: 3".to_vec(),
+        ];
+        for raw in malformed {
+            let err = receive_response(&mut raw.as_slice(), &strict, None).unwrap_err();
+            assert_eq!(err.kind(), &ErrorKind::ParseError, "{}", String::from_utf8_lossy(&raw));
+            assert!(receive_response(&mut raw.as_slice(), &test_request(), None).is_ok());
+        }
+        let raw = "HTTP/1.1 200 OK\r\nContent-Length: 3\r\ncontent-length: 3\r\n\r\nabc";
+        assert_eq!(receive_response(&mut raw.as_bytes(), &strict, None).unwrap().body, "abc");
+    }
+
+    #[test]
+    fn test_http_1_0_response_reads_until_close() {
+        let raw = "HTTP/1.0 200 OK\r\nContent-Type: text/plain\r\n\r\nall of\r\nthis";
+        let mut request = test_request();
+        request.add_header("connection", "keep-alive");
+        let response = receive_response(&mut raw.as_bytes(), &request, None).unwrap();
+        assert_eq!(response.body, "all of\r\nthis");
+        let raw = "HTTP/1.0 200 OK\r\nConnection: keep-alive\r\nTransfer-Encoding: chunked\r\nContent-Length: 1\r\n\r\n3\r\nabc";
+        let response = receive_response(&mut raw.as_bytes(), &test_request(), None).unwrap();
+        assert_eq!(response.body, "3\r\nabc");
+        assert!(response.trailers().is_none());
+    }
+
+    #[test]
+    fn test_max_headers() {
+        let mut request = test_request();
+        request.max_headers(2);
+        let raw = "HTTP/1.1 200 OK\r\nA: 1\r\nB: 2\r\n\r\n";
+        assert!(receive_response(&mut raw.as_bytes(), &request, None).is_ok());
+        let raw = "HTTP/1.1 200 OK\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n";
+        let err = receive_response(&mut raw.as_bytes(), &request, None).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ParseError);
+        let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n0\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n";
+        let err = receive_response(&mut raw.as_bytes(), &request, None).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ParseError);
+        let raw = format!("HTTP/1.1 200 OK\r\n{}\r\n", "A: 1\r\n".repeat(100));
+        assert!(receive_response(&mut raw.as_bytes(), &test_request(), None).is_ok());
+    }
+
+    #[test]
+    fn test_zero_content_length_does_not_over_read() {
+        let next = "HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nabc";
+        let raw = format!("HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n{}", next);
+        let mut stream = raw.as_bytes();
+        let response = receive_response(&mut stream, &test_request(), None).unwrap();
+        assert_eq!(response.body, "");
+        assert_eq!(stream, next.as_bytes());
+        let response = receive_response(&mut stream, &test_request(), None).unwrap();
+        assert_eq!(response.body, "abc");
+        assert!(stream.is_empty());
+    }
+
+    #[test]
+    fn test_no_content_does_not_over_read() {
+        let raw = "HTTP/1.1 204 No Content\r\n\r\nHTTP/1.1 200 OK\r\n\r\n";
+        let mut stream = raw.as_bytes();
+        let response = receive_response(&mut stream, &test_request(), None).unwrap();
+        assert_eq!(response.body_len(), 0);
+        assert_eq!(stream, b"HTTP/1.1 200 OK\r\n\r\n");
+    }
+
+    #[test]
+    fn test_read_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/", port)).unwrap();
+        request.read_timeout(Duration::from_millis(100));
+        let err = request_http_get(&request).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Timeout);
+    }
+
+    #[test]
+    fn test_execute_into_reuses_buffer() {
+        let (port, server) = serve(vec![b"HTTP/1.1 404 Not Found\r\nContent-Length: 4\r\n\r\ngone"]);
+        let request = Request::default_get_request(format!("http://127.0.0.1:{}/", port)).unwrap();
+        let mut body = b"stale data".to_vec();
+        let status = request.execute_into(&mut body).unwrap();
+        assert_eq!(status.0.get_code(), Some(404));
+        assert_eq!(status.1.as_deref(), Some("Not Found"));
+        assert_eq!(body, b"gone");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_follow_redirect() {
+        let (port, server) = serve(vec![b"HTTP/1.1 200 OK\r\n\r\nredirected"]);
+        let raw = "HTTP/1.1 302 Found\r\nLocation: next?a=1\r\n\r\n";
+        let request = Request::default_get_request(format!("http://127.0.0.1:{}/start/here", port)).unwrap();
+        let response = receive_response(&mut raw.as_bytes(), &request, None).unwrap();
+        let next = response.follow().unwrap().unwrap();
+        assert_eq!(next.body, "redirected");
+        assert!(next.follow().is_none());
+        assert!(server.join().unwrap()[0].starts_with("GET /start/next?a=1 HTTP/1.1\r\n"));
+    }
 }
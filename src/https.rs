@@ -1,13 +1,20 @@
 //! This module relates to the HTTPS GET using OpenSSL.
 extern crate openssl;
 
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::io::Write;
 use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-use openssl::ssl::{SslConnector, SslMethod, SslStream};
+use openssl::ssl::{HandshakeError, NameType, SslConnector, SslConnectorBuilder, SslMethod, SslSession,
+                   SslSessionCacheMode, SslStream};
 
-use super::{Request, Response, ToUrl, Url};
+use super::{Request, Response, ResponseStatus, ToUrl};
 use super::errors::NanoGetError;
 use super::http;
+use super::stream::ResponseLines;
 use crate::errors::ErrorKind;
 
 /// The implementation of HTTPS GET using OpenSSL.
@@ -20,14 +27,252 @@ pub fn get_https<A: ToUrl>(url: A) -> String {
     response.body
 }
 
-fn acquire_ssl_stream(url: &Url) -> Result<SslStream<TcpStream>, NanoGetError> {
+fn acquire_ssl_stream(request: &Request, deadline: Option<Instant>) -> Result<SslStream<TcpStream>, NanoGetError> {
+    let stream = http::connect(request, deadline)?;
+    match request.tls_sessions.as_ref() {
+        Some(sessions) => sessions.connect(stream, &request.url.host),
+        None => establish_tls(stream, &request.url.host),
+    }
+}
+
+/// The TLS sessions of a `Client`, kept to resume them when connecting to the same host again,
+/// which saves a full handshake.
+///
+/// The sessions are only resumed with the connector that created them, which is shared by all the
+/// requests of the client. Sessions are stored by host name, as OpenSSL hands them over (including
+/// the session tickets a TLS 1.3 server sends after the handshake). They are kept encoded, because
+/// OpenSSL marks a session as not resumable when its connection is dropped without a TLS shutdown,
+/// which is how every connection of a request ends.
+#[derive(Clone)]
+pub(crate) struct TlsSessions {
+    connector: SslConnector,
+    sessions: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl TlsSessions {
+    pub(crate) fn new() -> Result<TlsSessions, NanoGetError> {
+        let builder = SslConnector::builder(SslMethod::tls()).map_err(|err| NanoGetError::with_source(ErrorKind::HttpsSslError, err))?;
+        Ok(TlsSessions::from_builder(builder))
+    }
+
+    fn from_builder(mut builder: SslConnectorBuilder) -> TlsSessions {
+        let sessions = Arc::new(Mutex::new(HashMap::new()));
+        let store = Arc::clone(&sessions);
+        builder.set_session_cache_mode(SslSessionCacheMode::CLIENT);
+        builder.set_new_session_callback(move |ssl, session| {
+            if let (Some(host), Ok(der), Ok(mut sessions)) = (ssl.servername(NameType::HOST_NAME), session.to_der(), store.lock()) {
+                sessions.insert(host.to_string(), der);
+            }
+        });
+        TlsSessions { connector: builder.build(), sessions }
+    }
+
+    /// Performs the TLS handshake like `establish_tls`, resuming the last session with the host if
+    /// there is one. A server that declines to resume it gets a full handshake instead.
+    fn connect(&self, stream: TcpStream, host: &str) -> Result<SslStream<TcpStream>, NanoGetError> {
+        let ssl_error = |err| NanoGetError::with_source(ErrorKind::HttpsSslError, err);
+        let mut config = self.connector.configure().map_err(ssl_error)?;
+        let session = self.sessions.lock().ok()
+            .and_then(|sessions| sessions.get(host).and_then(|der| SslSession::from_der(der).ok()));
+        if let Some(session) = session {
+            // SAFETY: the session was negotiated by this connector, and so with the same context.
+            unsafe { config.set_session(&session) }.map_err(ssl_error)?;
+        }
+        config.connect(host, stream).map_err(handshake_error)
+    }
+}
+
+impl Debug for TlsSessions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let count = self.sessions.lock().map_or(0, |sessions| sessions.len());
+        write!(f, "TlsSessions {{ sessions: {} }}", count)
+    }
+}
+
+/// Performs the TLS handshake over the connection, verifying the certificate against the host.
+///
+/// Fails with an error of kind `HttpsSslError` if the handshake fails (including when the
+/// connection is reset during the handshake), with the OpenSSL error as its source. Failures to
+/// open the TCP connection itself are errors of kind `NetworkError` from `connect_tcp`.
+///
+/// ## Example
+/// ```rust,no_run
+/// use nano_get::{connect_tcp, establish_tls, resolve, Request};
+///
+/// let request = Request::default_get_request("https://example.com/").unwrap();
+/// let addrs = resolve(&request.url).unwrap();
+/// let stream = connect_tcp(&addrs, &request, None).unwrap();
+/// let tls_stream = establish_tls(stream, &request.url.host).unwrap();
+/// ```
+pub fn establish_tls(stream: TcpStream, host: &str) -> Result<SslStream<TcpStream>, NanoGetError> {
     let connector: SslConnector = SslConnector::builder(SslMethod::tls())
-        .map_err(|_err| NanoGetError::new(ErrorKind::HttpsSslError))?.build();
-    let stream = TcpStream::connect(&url.get_host_with_port()).unwrap();
-    connector.connect(&url.host, stream).map_err(|_err| NanoGetError::new(ErrorKind::HttpsSslError))
+        .map_err(|err| NanoGetError::with_source(ErrorKind::HttpsSslError, err))?.build();
+    connector.connect(host, stream).map_err(handshake_error)
+}
+
+/// Maps a failed handshake to a `HttpsSslError`, keeping the underlying OpenSSL error as its source
+/// (without the half-open stream).
+fn handshake_error(err: HandshakeError<TcpStream>) -> NanoGetError {
+    match err {
+        HandshakeError::SetupFailure(err) => NanoGetError::with_source(ErrorKind::HttpsSslError, err),
+        HandshakeError::Failure(mid) | HandshakeError::WouldBlock(mid) => {
+            NanoGetError::with_source(ErrorKind::HttpsSslError, mid.into_error())
+        }
+    }
+}
+
+/// Returns the certificate the server presented during the handshake, DER-encoded.
+fn peer_certificate(ssl_stream: &SslStream<TcpStream>) -> Option<Vec<u8>> {
+    ssl_stream.ssl().peer_certificate().and_then(|cert| cert.to_der().ok())
 }
 
 pub fn request_https_get(request: &Request) -> Result<Response, NanoGetError> {
-    let mut ssl_stream = acquire_ssl_stream(&request.url)?;
-    http::execute(&mut ssl_stream, &request)
-}
\ No newline at end of file
+    let deadline = request.get_deadline();
+    let ssl_stream = acquire_ssl_stream(request, deadline)?;
+    let peer_addr = ssl_stream.get_ref().peer_addr().ok();
+    let peer_certificate = peer_certificate(&ssl_stream);
+    let mut response = http::execute(http::timed(ssl_stream, request, deadline)?, request, deadline)?;
+    response.set_peer_addr(peer_addr);
+    response.set_peer_certificate(peer_certificate);
+    Ok(response)
+}
+
+pub fn request_https_get_into(request: &Request, body: &mut Vec<u8>) -> Result<ResponseStatus, NanoGetError> {
+    let deadline = request.get_deadline();
+    let ssl_stream = acquire_ssl_stream(request, deadline)?;
+    http::execute_into(http::timed(ssl_stream, request, deadline)?, request, deadline, body)
+}
+
+pub fn request_https_lines(request: &Request) -> Result<ResponseLines, NanoGetError> {
+    let deadline = request.get_deadline();
+    let ssl_stream = acquire_ssl_stream(request, deadline)?;
+    http::execute_lines(ssl_stream, request, deadline)
+}
+
+pub fn request_https_head_only(request: &Request) -> Result<Response, NanoGetError> {
+    let deadline = request.get_deadline();
+    let ssl_stream = acquire_ssl_stream(request, deadline)?;
+    let peer_addr = ssl_stream.get_ref().peer_addr().ok();
+    let peer_certificate = peer_certificate(&ssl_stream);
+    let mut response = http::execute_head_only(http::timed(ssl_stream, request, deadline)?, request, deadline)?;
+    response.set_peer_addr(peer_addr);
+    response.set_peer_certificate(peer_certificate);
+    Ok(response)
+}
+
+pub fn request_https_get_to_writer(request: &Request, writer: &mut dyn Write) -> Result<ResponseStatus, NanoGetError> {
+    let deadline = request.get_deadline();
+    let ssl_stream = acquire_ssl_stream(request, deadline)?;
+    http::execute_to_writer(http::timed(ssl_stream, request, deadline)?, request, deadline, writer)
+}
+
+impl http::HasSocket for SslStream<TcpStream> {
+    fn socket(&self) -> &TcpStream {
+        self.get_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use openssl::asn1::Asn1Time;
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+    use openssl::ssl::{SslAcceptor, SslVerifyMode};
+    use openssl::x509::{X509, X509NameBuilder};
+
+    use super::*;
+
+    #[test]
+    fn test_failed_handshake_is_an_ssl_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").unwrap();
+        });
+        let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let err = establish_tls(stream, "localhost").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::HttpsSslError);
+        assert!(std::error::Error::source(&err).is_some());
+        server.join().unwrap();
+    }
+
+    /// Serves `connections` connections over TLS with a new self-signed certificate for
+    /// `localhost`, reading the head of the request on each and answering with the response.
+    /// Returns the port and the DER-encoded certificate.
+    fn serve_tls(connections: usize, response: &'static [u8]) -> (u16, Vec<u8>, thread::JoinHandle<()>) {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "localhost").unwrap();
+        let name = name.build();
+        let mut cert = X509::builder().unwrap();
+        cert.set_subject_name(&name).unwrap();
+        cert.set_issuer_name(&name).unwrap();
+        cert.set_pubkey(&key).unwrap();
+        cert.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        cert.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        cert.sign(&key, MessageDigest::sha256()).unwrap();
+        let cert = cert.build();
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor.set_private_key(&key).unwrap();
+        acceptor.set_certificate(&cert).unwrap();
+        let acceptor = acceptor.build();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            for _ in 0..connections {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(acceptor.accept(stream).unwrap());
+                let mut head = String::new();
+                while reader.read_line(&mut head).unwrap() > 0 && !head.ends_with("\r\n\r\n") {}
+                let stream = reader.get_mut();
+                stream.write_all(response).unwrap();
+                stream.shutdown().unwrap();
+            }
+        });
+        (port, cert.to_der().unwrap(), server)
+    }
+
+    /// Returns sessions with a connector that accepts any certificate, like the self-signed one of
+    /// `serve_tls`.
+    fn unverified_sessions() -> TlsSessions {
+        let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+        builder.set_verify(SslVerifyMode::NONE);
+        TlsSessions::from_builder(builder)
+    }
+
+    #[test]
+    fn test_tls_session_is_resumed() {
+        let (port, _, server) = serve_tls(2, b"hi");
+        let sessions = unverified_sessions();
+        let mut reused = Vec::new();
+        for _ in 0..2 {
+            let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            let mut stream = sessions.connect(stream, "localhost").unwrap();
+            stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+            let mut body = String::new();
+            stream.read_to_string(&mut body).unwrap();
+            assert_eq!(body, "hi");
+            reused.push(stream.ssl().session_reused());
+        }
+        server.join().unwrap();
+        assert_eq!(reused, vec![false, true]);
+    }
+
+    #[test]
+    fn test_peer_certificate() {
+        let (port, cert, server) = serve_tls(1, b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+        let mut request = Request::default_get_request(format!("https://127.0.0.1:{}/", port)).unwrap();
+        request.tls_sessions = Some(unverified_sessions());
+        let response = request.execute().unwrap();
+        server.join().unwrap();
+        assert_eq!(response.body, "ok");
+        assert_eq!(response.peer_certificate(), Some(cert.as_slice()));
+    }
+}
@@ -5,7 +5,7 @@
 //! ## Quick Example
 //!
 //! An example usage is shown below:
-//! ```rust
+//! ```rust,no_run
 //! let response = nano_get::get_http("http://example.com");
 //! println!("{}", response);
 //! ```
@@ -28,18 +28,31 @@
 //! incase the "https" feature flag is not enabled.
 //!
 //! An example usage of the unified get is shown below:
-//! ```rust
+//! ```rust,no_run
 //! let response = nano_get::get("http://dummy.restapiexample.com/api/v1/employees");
 //! println!("{}", response);
 //! ```
 //!
 //! or, with the "https" feature flag enabled and the OpenSSL library present,
 //!
-//! ```rust
+//! ```rust,no_run
 //! let response = nano_get::get("https://www.google.com");
 //! println!("{}", response);
 //! ```
 //!
+//! ## Logging
+//!
+//! With the "log" feature flag enabled (NOT activated by default), requests and responses are logged
+//! through the [log](https://crates.io/crates/log) facade: the request line and response status at
+//! debug level, and the headers at trace level. The values of headers carrying credentials
+//! (`Authorization`, `Proxy-Authorization`, `Cookie` and `Set-Cookie`) are redacted.
+//!
+//! ## Socket Options
+//!
+//! With the "socket-config" feature flag enabled (NOT activated by default), a `SocketConfig` can also
+//! set TCP keep-alive, the socket buffer sizes and the local address to connect from, through the
+//! [socket2](https://crates.io/crates/socket2) crate. Without it, only `TCP_NODELAY` can be set.
+//!
 //! ## Executing HTTP(s) Requests:
 //!
 //! There are two ways to execute the HTTP(s) requests.
@@ -50,7 +63,7 @@
 //! and returns the body of the response.
 //!
 //! #### Example
-//! ```rust
+//! ```rust,no_run
 //! let response = nano_get::get("https://www.google.com");
 //! println!("{}", response);
 //! ```
@@ -62,29 +75,45 @@
 //! `nano_get::Response` object. This allows inspection of HTTP Response codes, response body, etc.
 //!
 //! #### Example
-//! ```rust
+//! ```rust,no_run
 //! use nano_get::Response;
 //! let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
 //! let response: Response = request.execute().unwrap();
 //! ```
 //!
 //! For details, check the `Request` and `Response` structure documentation.
-pub use http::get_http;
+pub use client::Client;
+pub use errors::{ErrorKind, NanoGetError};
+pub use http::{connect_tcp, get_http, resolve};
 #[cfg(feature = "https")]
-pub use https::get_https;
-pub use request::{Header, Request};
-pub use response::{Response, ResponseStatus, StatusCode};
-pub use url::{ToUrl, Url};
+pub use https::{establish_tls, get_https};
+pub use multipart::{MultipartBuilder, MultipartForm};
+pub use request::{BodyMode, DefaultHeaders, Header, HeaderHook, RedirectHook, RedirectResponseHook, Request};
+pub use response::{AuthChallenge, InformationalResponse, MultipartPart, RawExchange, Response, ResponseStatus,
+                   StatusCode};
+pub use socket::SocketConfig;
+pub use stream::ResponseLines;
+pub use url::{encode_form_component, encode_query_component, ToUrl, Url};
 
 mod url;
+mod client;
+mod date;
+mod digest;
 mod http;
+mod multipart;
+mod random;
 mod request;
 mod response;
+mod socket;
+mod stream;
 mod errors;
 
 #[cfg(feature = "https")]
 mod https;
 
+#[cfg(test)]
+mod testing;
+
 /// This is a unified function for the HTTP GET method.
 ///
 /// This calls the http version of GET provided in this crate by default.
@@ -103,7 +132,7 @@ pub fn get<U: ToUrl>(url: U) -> String {
     let protocol = &url.protocol[..];
 
     #[cfg(feature = "https")] {
-        if protocol.eq("https") {
+        if protocol.eq_ignore_ascii_case("https") {
             return get_https(&url);
         }
     }
@@ -113,8 +142,6 @@ pub fn get<U: ToUrl>(url: U) -> String {
 
 #[cfg(test)]
 mod tests {
-    use url;
-
     use super::*;
 
     #[test]
@@ -148,4 +175,161 @@ mod tests {
         println!("{}, {}", a, b);
         assert_eq!(a, "http".to_string());
     }
+
+    #[test]
+    fn test_url_join() {
+        let base = Url::new("http://example.com:8080/a/b/c?d=1");
+        assert_eq!(base.join("e").unwrap().get_full_url(), "http://example.com:8080/a/b/e");
+        assert_eq!(base.join("./e/../f").unwrap().path, "/a/b/f");
+        assert_eq!(base.join("../../../e").unwrap().path, "/e");
+        assert_eq!(base.join("/e?f=2#frag").unwrap().path, "/e?f=2");
+        assert_eq!(base.join("?x=y").unwrap().path, "/a/b/c?x=y");
+        assert_eq!(base.join("").unwrap().path, "/a/b/c?d=1");
+        let other = base.join("//other.com/p").unwrap();
+        assert_eq!((other.protocol.as_str(), other.host.as_str(), other.port.as_str()), ("http", "other.com", "80"));
+        let absolute = base.join("https://secure.com/").unwrap();
+        assert_eq!((absolute.protocol.as_str(), absolute.port.as_str()), ("https", "443"));
+        assert!(base.join("/bad path").is_err());
+        let login = base.join("/r?u=http://evil.com/x").unwrap();
+        assert_eq!((login.host.as_str(), login.path.as_str()), ("example.com", "/r?u=http://evil.com/x"));
+        let next = base.join("c?next=https://x.y").unwrap();
+        assert_eq!((next.host.as_str(), next.path.as_str()), ("example.com", "/a/b/c?next=https://x.y"));
+        let absolute = base.join("https://secure.com/r?u=http://evil.com/x").unwrap();
+        assert_eq!((absolute.host.as_str(), absolute.path.as_str()), ("secure.com", "/r?u=http://evil.com/x"));
+        let mut empty = Url::new("http://example.com/");
+        empty.path = String::new();
+        assert_eq!(empty.join("e").unwrap().path, "/e");
+    }
+
+    #[test]
+    fn test_url_from_host_port() {
+        let url = Url::from_host_port("127.0.0.1", 3000);
+        assert_eq!((url.protocol.as_str(), url.host.as_str(), url.port.as_str(), url.path.as_str()),
+                   ("http", "127.0.0.1", "3000", "/"));
+        let url = Url::from_host_port("::1", 8080);
+        assert_eq!(url.get_host_with_port(), "[::1]:8080");
+    }
+
+    #[test]
+    fn test_url_with_scheme() {
+        let url = Url::new("http://example.com/a/b?c=1");
+        let secure = url.with_scheme("https").unwrap();
+        assert_eq!((secure.protocol.as_str(), secure.port.as_str(), secure.path.as_str()),
+                   ("https", "443", "/a/b?c=1"));
+        assert_eq!(secure.as_absolute(), "https://example.com/a/b?c=1");
+        assert_eq!(secure.with_scheme("HTTP").unwrap().as_absolute(), url.as_absolute());
+        let custom = Url::new("http://example.com:8080/x").with_scheme("https").unwrap();
+        assert_eq!((custom.port.as_str(), custom.path.as_str()), ("8080", "/x"));
+        assert_eq!(url.with_scheme("ws").unwrap().port, "80");
+        assert!(url.with_scheme("1http").is_err());
+        assert!(url.with_scheme("").is_err());
+    }
+
+    #[test]
+    fn test_url_bare_host_port() {
+        let url = Url::new("127.0.0.1:3000");
+        assert_eq!((url.protocol.as_str(), url.host.as_str(), url.port.as_str(), url.path.as_str()),
+                   ("http", "127.0.0.1", "3000", "/"));
+        let url = Url::new("localhost:3000/status?verbose=1");
+        assert_eq!((url.host.as_str(), url.port.as_str(), url.path.as_str()),
+                   ("localhost", "3000", "/status?verbose=1"));
+        let url = Url::new("[::1]:3000/x");
+        assert_eq!((url.host.as_str(), url.port.as_str(), url.path.as_str()), ("[::1]", "3000", "/x"));
+        let url = Url::new("https://[::1]/");
+        assert_eq!((url.host.as_str(), url.port.as_str()), ("[::1]", "443"));
+    }
+
+    #[test]
+    fn test_url_parse_many() {
+        let inputs = ["http://example.com/", "", "   ", "http://", "example.com:99999", "http://a b/",
+            "://x", "1http://x/", "https://[::1]:8443/p", ":::", "http://host:/", "\u{0}"];
+        let results = Url::parse_many(inputs.iter());
+        let valid: Vec<bool> = results.iter().map(|r| r.is_ok()).collect();
+        assert_eq!(valid, vec![true, false, false, false, false, false, false, false, true, false, false, false]);
+    }
+
+    #[test]
+    fn test_url_as_absolute_and_normalized() {
+        let url = Url::new("http://example.com/a/./b");
+        assert_eq!(url.as_absolute(), "http://example.com/a/./b");
+        assert_eq!(url.get_full_url(), "http://example.com:80/a/./b");
+        assert_eq!(url.normalized(), "http://example.com/a/b");
+        assert_eq!(Url::new("HTTPS://[::1]:8443").normalized(), "https://[::1]:8443/");
+        assert_eq!(Url::new("HTTPS://Example.com/").normalized(), "https://example.com/");
+        let joined = url.join("../c?d=1").unwrap();
+        assert_eq!(joined.as_absolute(), joined.normalized());
+    }
+
+    #[test]
+    fn test_url_is_fetchable() {
+        for url in &["http://example.com/", "HTTP://example.com", "http://127.0.0.1:3000/x?y=1", "[::1]:8080"] {
+            assert!(Url::new(url).is_fetchable(), "{}", url);
+        }
+        for url in &["ftp://example.com/", "http://", "http://:80/", "http://example.com:0/",
+            "http://example.com:port/", "http://exa mple.com/", "http://example.com/a b"] {
+            assert!(!Url::new(url).is_fetchable(), "{}", url);
+        }
+        assert_eq!(Url::new("https://example.com/").is_fetchable(), cfg!(feature = "https"));
+        let upper = Url::new("HTTPS://example.com/");
+        assert_eq!(upper.is_fetchable(), cfg!(feature = "https"));
+        let request = Request::default_get_request(&upper).unwrap();
+        assert!(request.is_https());
+        assert_eq!(request.port(), 443);
+    }
+
+    #[test]
+    fn test_url_set_path_and_query() {
+        let mut url = Url::new("http://example.com:8080/a?x=1#frag");
+        url.set_path("/b c/%41?d#e");
+        assert_eq!(url.path, "/b%20c/%41%3Fd%23e?x=1");
+        url.set_query(&[("k", "a&b")]);
+        assert_eq!(url.path, "/b%20c/%41%3Fd%23e?k=a%26b");
+        assert_eq!(url.as_absolute(), "http://example.com:8080/b%20c/%41%3Fd%23e?k=a%26b");
+        url.set_path("");
+        assert_eq!(url.path, "/?k=a%26b");
+    }
+
+    #[test]
+    fn test_query_is_added_before_the_fragment() {
+        let mut url = Url::new("http://example.com/p#frag");
+        url.add_query_param("q", "1");
+        assert_eq!(url.path, "/p?q=1");
+        let mut url = Url::new("http://example.com/p?a=1#frag");
+        url.add_query_param("q", "1");
+        assert_eq!(url.path, "/p?a=1&q=1");
+        let mut url = Url::new("http://example.com/p#frag");
+        url.set_query(&[("q", "1")]);
+        assert_eq!(url.as_absolute(), "http://example.com/p?q=1");
+        let mut url = Url::new("http://example.com/p#frag");
+        url.set_query(&[]);
+        assert_eq!(url.path, "/p");
+    }
+
+    #[test]
+    fn test_query_and_form_encoding() {
+        assert_eq!(encode_query_component("1 + 1"), "1%20%2B%201");
+        assert_eq!(encode_form_component("1 + 1"), "1+%2B+1");
+        let mut url = Url::new("http://example.com/");
+        url.add_query_param("sum", "1 + 1");
+        assert_eq!(url.query_pairs(), vec![("sum".to_string(), "1 + 1".to_string())]);
+    }
+
+    #[test]
+    fn test_url_query_order_and_canonical_form() {
+        let mut url = Url::new("http://example.com/api?z=last&a=first");
+        url.add_query_param("m", "a b/c+d");
+        url.add_query_param("a", "0");
+        assert_eq!(url.query(), Some("z=last&a=first&m=a%20b%2Fc%2Bd&a=0"));
+        let pairs: Vec<(String, String)> = url.query_pairs();
+        assert_eq!(pairs, vec![
+            ("z".to_string(), "last".to_string()),
+            ("a".to_string(), "first".to_string()),
+            ("m".to_string(), "a b/c+d".to_string()),
+            ("a".to_string(), "0".to_string()),
+        ]);
+        assert_eq!(url.canonical_query(), "a=0&a=first&m=a%20b%2Fc%2Bd&z=last");
+        let reencoded = Url::new("http://example.com/api?m=a%20b%2fc%2bd&z=last&a=first&a=0");
+        assert_eq!(reencoded.canonical_query(), url.canonical_query());
+        assert_eq!(Url::new("http://example.com/").canonical_query(), "");
+    }
 }
@@ -0,0 +1,143 @@
+//! This module provides the `MultipartBuilder`, for building `multipart/form-data` request bodies.
+use super::random::random_u64;
+
+/// Builds a `multipart/form-data` body, as sent by HTML forms that upload files.
+///
+/// Add the text fields and files in the order they are to be sent, then `build` the form and set
+/// it as the body of a request with `Request::multipart_body`.
+///
+/// ## Example
+/// ```rust,no_run
+/// use nano_get::{MultipartBuilder, Request};
+///
+/// let mut builder = MultipartBuilder::new();
+/// builder.add_text("title", "Holiday");
+/// builder.add_file("photo", "beach.png", "image/png", &[0x89, b'P', b'N', b'G']);
+///
+/// let mut request = Request::default_get_request("http://example.com/upload").unwrap();
+/// request.multipart_body(builder.build());
+/// let response = request.execute().unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MultipartBuilder {
+    parts: Vec<FormPart>,
+}
+
+#[derive(Debug, Clone)]
+struct FormPart {
+    name: String,
+    file: Option<(String, String)>,
+    data: Vec<u8>,
+}
+
+impl MultipartBuilder {
+    /// Creates a builder for an empty form.
+    pub fn new() -> Self {
+        MultipartBuilder { parts: Vec::new() }
+    }
+
+    /// Adds a text field with the given name and value.
+    pub fn add_text(&mut self, name: &str, value: &str) {
+        self.parts.push(FormPart { name: name.to_string(), file: None, data: value.as_bytes().to_vec() });
+    }
+
+    /// Adds a file with the given field name, file name, content type and contents.
+    ///
+    /// Quotes and line breaks in the names and the content type are percent-encoded, as browsers
+    /// do, so they can't break out of their headers.
+    pub fn add_file(&mut self, name: &str, filename: &str, content_type: &str, data: &[u8]) {
+        let file = Some((filename.to_string(), content_type.to_string()));
+        self.parts.push(FormPart { name: name.to_string(), file, data: data.to_vec() });
+    }
+
+    /// Builds the form, with a random boundary that does not occur in any of its parts.
+    pub fn build(&self) -> MultipartForm {
+        loop {
+            let boundary = format!("nano-get-{:016x}", random_u64());
+            let delimiter = format!("--{}", boundary);
+            let clashes = self.parts.iter()
+                .any(|part| part.data.windows(delimiter.len()).any(|window| window == delimiter.as_bytes()));
+            if !clashes {
+                return self.build_with_boundary(boundary);
+            }
+        }
+    }
+
+    fn build_with_boundary(&self, boundary: String) -> MultipartForm {
+        let mut body = Vec::new();
+        for part in self.parts.iter() {
+            body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+            let mut disposition = format!("Content-Disposition: form-data; name=\"{}\"", escape(&part.name));
+            if let Some((filename, content_type)) = part.file.as_ref() {
+                disposition += &format!("; filename=\"{}\"\r\nContent-Type: {}", escape(filename), escape(content_type));
+            }
+            body.extend_from_slice(disposition.as_bytes());
+            body.extend_from_slice(b"\r\n\r\n");
+            body.extend_from_slice(&part.data);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+        MultipartForm { boundary, body }
+    }
+}
+
+/// A `multipart/form-data` body built by a `MultipartBuilder`, along with its boundary.
+#[derive(Debug, Clone)]
+pub struct MultipartForm {
+    boundary: String,
+    pub(crate) body: Vec<u8>,
+}
+
+impl MultipartForm {
+    /// Returns the boundary that separates the parts of the form.
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Returns the value of the `Content-Type` header to send the form with, like
+    /// `multipart/form-data; boundary=nano-get-0123456789abcdef`.
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Returns the encoded body of the form.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('"', "%22").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_form_framing() {
+        let mut builder = MultipartBuilder::new();
+        builder.add_text("title", "a\r\nb");
+        builder.add_file("up\"load", "x.bin", "application/octet-stream", &[0x00, 0xff]);
+        let form = builder.build_with_boundary("sep".to_string());
+        let mut expected = b"--sep\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\na\r\nb\r\n\
+            --sep\r\nContent-Disposition: form-data; name=\"up%22load\"; filename=\"x.bin\"\r\n\
+            Content-Type: application/octet-stream\r\n\r\n".to_vec();
+        expected.extend_from_slice(&[0x00, 0xff]);
+        expected.extend_from_slice(b"\r\n--sep--\r\n");
+        assert_eq!(form.body(), expected.as_slice());
+        assert_eq!(form.content_type(), "multipart/form-data; boundary=sep");
+        assert_eq!(MultipartBuilder::new().build_with_boundary("sep".to_string()).body(), b"--sep--\r\n");
+    }
+
+    #[test]
+    fn test_boundary_does_not_occur_in_the_parts() {
+        let mut builder = MultipartBuilder::new();
+        builder.add_text("a", "1");
+        let form = builder.build();
+        assert!(form.boundary().starts_with("nano-get-"));
+        assert!(form.body().starts_with(format!("--{}\r\n", form.boundary()).as_bytes()));
+        assert!(form.body().ends_with(format!("\r\n--{}--\r\n", form.boundary()).as_bytes()));
+        assert_ne!(builder.build().boundary(), form.boundary());
+    }
+}
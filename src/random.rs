@@ -0,0 +1,11 @@
+//! This module provides the random numbers used for boundaries, nonces and request ids.
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// Returns a random number, seeded from the random keys that std draws for each `HashMap`.
+///
+/// This is not a cryptographically secure generator, but is unpredictable enough for values that
+/// only have to be unique, like multipart boundaries and client nonces.
+pub(crate) fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
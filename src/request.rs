@@ -1,12 +1,24 @@
 use std::collections::HashMap;
 use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
 use super::{ToUrl, Url};
-use super::errors::NanoGetError;
-use super::http::request_http_get;
+use super::digest;
+use super::errors::{ErrorKind, NanoGetError};
+use super::http::{connect_tunnel, resolve, request_http_get, request_http_get_into, request_http_get_to_writer,
+                   request_http_head_only, request_http_lines};
 #[cfg(feature = "https")]
-use super::https::request_https_get;
-use super::Response;
+use super::https::{request_https_get, request_https_get_into, request_https_get_to_writer, request_https_head_only,
+                   request_https_lines, TlsSessions};
+use super::multipart::MultipartForm;
+use super::socket::SocketConfig;
+use super::stream::ResponseLines;
+use super::{Response, ResponseStatus};
 
 /// This is the basic HTTP Request Object.
 ///
@@ -52,24 +64,147 @@ use super::Response;
 ///
 /// ### Example
 /// For regular HTTP GET requests,
-/// ```rust
+/// ```rust,no_run
 /// use nano_get::Response;
 /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
 /// request.add_header("test", "value testing");
 /// let response: Response = request.execute().unwrap();
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Request {
     /// The embedded Url that is part of the request. This is used while executing the HTTP Request.
     pub url: Url,
     request_type: RequestType,
     headers: Option<HashMap<String, String>>,
     /// The optional body of the request, that is sent while executing the request.
+    ///
+    /// `None` sends no body and no `Content-Length`, while an empty body (`Some(String::new())`)
+    /// is sent with `Content-Length: 0`, as some servers require for requests without content.
     pub body: Option<String>,
+    connect_retries: u32,
+    connect_retry_delay: Duration,
+    body_read_budget: Option<Duration>,
+    cancel_flag: Option<Arc<AtomicBool>>,
+    strict: bool,
+    coalesce_writes: bool,
+    write_buffer_size: usize,
+    max_headers: usize,
+    max_response_bytes: Option<u64>,
+    max_download_rate: Option<u64>,
+    body_mode: BodyMode,
+    pub(crate) timeouts: Timeouts,
+    deadline: Option<Instant>,
+    pub(crate) socket_config: SocketConfig,
+    #[cfg(feature = "https")]
+    pub(crate) tls_sessions: Option<TlsSessions>,
+    body_reader: Option<BodyReader>,
+    body_bytes: Option<Vec<u8>>,
+    before_send: Option<BeforeSend>,
+    digest_auth: Option<DigestCredentials>,
+    max_redirects: usize,
+    redirect_policy: Option<RedirectPolicy>,
+    on_redirect: Option<OnRedirect>,
+    block_private_addresses: bool,
+    resolved: Option<Vec<SocketAddr>>,
+    expect_continue: bool,
+    require_https: bool,
+    collect_informational: bool,
+    capture_exchange: bool,
+    expected_statuses: Option<Vec<u16>>,
+    asterisk_target: bool,
+}
+
+/// A source for the body of a request, that is copied to the connection while the request is sent.
+#[derive(Clone)]
+pub(crate) struct BodyReader {
+    pub reader: Arc<Mutex<Box<dyn Read + Send>>>,
+    pub length: Option<u64>,
+}
+
+impl Debug for BodyReader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "BodyReader {{ length: {:?} }}", self.length)
+    }
+}
+
+/// The hook that is run over the final headers of a request, right before they are sent.
+#[derive(Clone)]
+struct BeforeSend(Arc<HeaderHook>);
+
+impl Debug for BeforeSend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "BeforeSend")
+    }
+}
+
+/// The policy that decides whether a redirect is followed.
+#[derive(Clone)]
+struct RedirectPolicy(Arc<RedirectHook>);
+
+impl Debug for RedirectPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RedirectPolicy")
+    }
+}
+
+/// The callback that is given each redirect response before it is followed.
+#[derive(Clone)]
+struct OnRedirect(Arc<RedirectResponseHook>);
+
+impl Debug for OnRedirect {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OnRedirect")
+    }
+}
+
+/// The credentials used to answer a Digest challenge. The password is never shown in logs.
+#[derive(Clone)]
+struct DigestCredentials {
+    username: String,
+    password: String,
+}
+
+impl Debug for DigestCredentials {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DigestCredentials {{ username: {:?} }}", self.username)
+    }
+}
+
+/// The timeouts of a request. Any timeout that is `None` is not enforced.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Timeouts {
+    pub connect: Option<Duration>,
+    pub read: Option<Duration>,
+    pub overall: Option<Duration>,
+}
+
+impl Timeouts {
+    /// Fills in the timeouts that are not set with the given defaults.
+    pub fn or(self, defaults: Timeouts) -> Timeouts {
+        Timeouts {
+            connect: self.connect.or(defaults.connect),
+            read: self.read.or(defaults.read),
+            overall: self.overall.or(defaults.overall),
+        }
+    }
+}
+
+/// Decides whether the body of the response is decoded as text into `Response::body`.
+///
+/// See `Request::body_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BodyMode {
+    /// Decode the body as text if the `Content-Type` of the response is textual.
+    #[default]
+    Auto,
+    /// Always decode the body as text.
+    Text,
+    /// Never decode the body as text, leaving `Response::body` empty.
+    Bytes,
 }
 
-#[allow(dead_code)]
-#[derive(Debug)]
+#[allow(dead_code, clippy::upper_case_acronyms)]
+#[derive(Debug, Clone)]
 enum RequestType {
     HEAD,
     GET,
@@ -94,9 +229,107 @@ impl RequestType {
     }
 }
 
+/// The default `user-agent` sent with every request, tracking the crate version.
+pub(crate) const USER_AGENT: &str = concat!("nano-get/", env!("CARGO_PKG_VERSION"));
+
+/// The built-in default `accept` header, sent unless the request (or its `Client`) sets another.
+pub(crate) const DEFAULT_ACCEPT: &str = "*/*";
+
+/// The maximum number of headers read from a response, unless configured otherwise.
+const DEFAULT_MAX_HEADERS: usize = 100;
+
+/// The size of the buffer requests are written through, unless configured otherwise.
+const DEFAULT_WRITE_BUFFER_SIZE: usize = 8 * 1024;
+
+/// The headers whose values are never shown when a `Request` is displayed.
+const REDACTED_HEADERS: [&str; 4] = ["authorization", "proxy-authorization", "cookie", "set-cookie"];
+
+/// Displays a summary of the request, suitable for logging: the method, full url and number of headers.
+///
+/// The alternate form (`{:#}`) also lists the headers, one per line. The values of the
+/// `Authorization`, `Proxy-Authorization`, `Cookie` and `Set-Cookie` headers are shown as `<redacted>`,
+/// so that credentials do not end up in logs.
+///
+/// ## Example
+/// ```rust
+/// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+/// request.add_header("authorization", "Bearer secret");
+/// assert_eq!(request.to_string(), "GET http://example.com:80/ (5 headers)");
+/// assert!(format!("{:#}", request).contains("authorization: <redacted>"));
+/// ```
+impl Display for Request {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut headers: Vec<(&str, &str)> = self.get_request_headers().collect();
+        write!(f, "{} {} ({} headers)", self.get_request_type(), self.url.get_full_url(), headers.len())?;
+        if f.alternate() {
+            headers.sort();
+            for (k, v) in headers {
+                write!(f, "\n{}: {}", k, redact_header_value(k, v))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the value of the header to show in logs, hiding the values of headers that carry credentials.
+pub(crate) fn redact_header_value<'a>(key: &str, value: &'a str) -> &'a str {
+    if REDACTED_HEADERS.iter().any(|h| h.eq_ignore_ascii_case(key)) {
+        "<redacted>"
+    } else {
+        value
+    }
+}
+
+/// A reusable set of headers, for applying the same headers (like the standard headers of an API)
+/// to many requests without a `Client`.
+///
+/// ## Example
+/// ```rust
+/// use nano_get::{DefaultHeaders, Request};
+///
+/// let mut api_headers = DefaultHeaders::new();
+/// api_headers.add("Accept", "application/json").unwrap();
+/// api_headers.add("Authorization", "Bearer token").unwrap();
+///
+/// let users = Request::with_defaults("http://example.com/users", &api_headers).unwrap();
+/// let groups = Request::with_defaults("http://example.com/groups", &api_headers).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DefaultHeaders {
+    headers: Vec<(String, String)>,
+}
+
+impl DefaultHeaders {
+    /// Creates an empty set of headers.
+    pub fn new() -> Self {
+        DefaultHeaders::default()
+    }
+
+    /// Adds a header to the set, overwriting any header with the same name (ignoring case).
+    ///
+    /// Like `Request::new`, this returns an error of kind `ParseError` if the header is not valid.
+    pub fn add(&mut self, key: &str, value: &str) -> Result<(), NanoGetError> {
+        validate_headers(Some(&vec![(key, value)]))?;
+        self.headers.retain(|(k, _)| !k.eq_ignore_ascii_case(key));
+        self.headers.push((key.to_string(), value.to_string()));
+        Ok(())
+    }
+}
+
 /// Coveneince wrapper for a tuple of (key: &str, value: &str) that is to be sent as a HTTP header.
 pub type Header<'a> = (&'a str, &'a str);
 
+/// A hook that changes the headers of a request right before it is sent. See `Request::before_send`.
+pub type HeaderHook = dyn Fn(&mut HashMap<String, String>) + Send + Sync;
+
+/// A hook that decides whether to follow a redirect from the current url to the next one. See
+/// `Request::redirect_policy`.
+pub type RedirectHook = dyn Fn(&Url, &Url) -> bool + Send + Sync;
+
+/// A callback that is given each redirect response and the url it redirects to, and decides whether
+/// to follow it. See `Request::on_redirect`.
+pub type RedirectResponseHook = dyn Fn(&Response, &Url) -> bool + Send + Sync;
+
 impl Request {
     /// Creates a new Request object, based on the url, and optional headers.
     ///
@@ -120,31 +353,77 @@ impl Request {
     /// let request_body = "Hello World!!".to_string();
     /// let request = Request::new("http://example.com", Some(request_headers), Some(request_body));
     /// ```
+    ///
+    /// The header names must be valid HTTP tokens and the values may only contain visible ASCII,
+    /// spaces and tabs. Otherwise, an error of kind `ParseError` is returned.
+    /// ```rust
+    /// use nano_get::Request;
+    /// let request_headers = vec![("bad header", "value1")];
+    /// assert!(Request::new("http://example.com", Some(request_headers), None).is_err());
+    /// ```
     pub fn new<A: ToUrl>(url: A, headers: Option<Vec<Header>>, body: Option<String>) -> Result<Self, Box<dyn Error>> {
-        let url = url.to_url()?;
+        Ok(Self::from_url(url.to_url()?, headers, body)?)
+    }
+
+    /// Creates a new Request object like `new`, taking ownership of an already parsed `Url`.
+    ///
+    /// Unlike `new`, the url is neither parsed nor cloned, which saves work when building many
+    /// requests in a loop.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::{Request, Url};
+    ///
+    /// let base = Url::new("http://example.com/api/");
+    /// for id in 1..=3 {
+    ///     let url = base.join(&format!("items/{}", id)).unwrap();
+    ///     let request = Request::from_url(url, None, None).unwrap();
+    /// }
+    /// ```
+    pub fn from_url(url: Url, headers: Option<Vec<Header>>, body: Option<String>) -> Result<Self, NanoGetError> {
+        validate_headers(headers.as_ref())?;
         let mut request = Request {
             url,
             request_type: RequestType::GET,
             headers: None,
             body,
+            connect_retries: 0,
+            connect_retry_delay: Duration::from_millis(0),
+            body_read_budget: None,
+            cancel_flag: None,
+            strict: false,
+            coalesce_writes: true,
+            write_buffer_size: DEFAULT_WRITE_BUFFER_SIZE,
+            max_headers: DEFAULT_MAX_HEADERS,
+            max_response_bytes: None,
+            max_download_rate: None,
+            body_mode: BodyMode::Auto,
+            timeouts: Timeouts::default(),
+            deadline: None,
+            socket_config: SocketConfig::default(),
+            #[cfg(feature = "https")]
+            tls_sessions: None,
+            body_reader: None,
+            body_bytes: None,
+            before_send: None,
+            digest_auth: None,
+            max_redirects: 0,
+            redirect_policy: None,
+            on_redirect: None,
+            block_private_addresses: false,
+            resolved: None,
+            expect_continue: false,
+            require_https: false,
+            collect_informational: false,
+            capture_exchange: false,
+            expected_statuses: None,
+            asterisk_target: false,
         };
         request.headers = Some(Self::get_default_headers(&request.url));
-        let addnl_headers = process_headers(headers);
-        request.merge_addnl_headers(addnl_headers);
-        Ok(request)
-    }
-
-    fn merge_addnl_headers(&mut self, addnl_headers: Option<HashMap<String, String>>) {
-        if self.headers.is_some() {
-            let headers = self.headers.as_mut().unwrap();
-            if let Some(extra_headers) = addnl_headers {
-                for (k, v) in extra_headers {
-                    headers.insert(k, v);
-                }
-            }
-        } else {
-            self.headers = addnl_headers;
+        if let Some(addnl_headers) = process_headers(headers) {
+            request.extend_headers(addnl_headers);
         }
+        Ok(request)
     }
 
     /// Simplified version to create a Request based only on the given Url.
@@ -162,10 +441,38 @@ impl Request {
         Self::new(url, None, None)
     }
 
+    /// Creates a Request like `default_get_request`, with the given headers added on top of the
+    /// default headers (overwriting them on conflict).
+    ///
+    /// See `DefaultHeaders` for an example.
+    pub fn with_defaults<A: ToUrl>(url: A, defaults: &DefaultHeaders) -> Result<Self, Box<dyn Error>> {
+        let mut request = Self::default_get_request(url)?;
+        request.extend_headers(defaults.headers.iter().cloned());
+        Ok(request)
+    }
+
+    /// Creates a server-wide `OPTIONS * HTTP/1.1` request to the host of the url, to query the
+    /// capabilities of the server as a whole rather than of a resource. The path of the url is
+    /// not sent.
+    ///
+    /// See `Response::allowed_methods` for the methods the server allows.
+    ///
+    /// ## Example
+    /// ```rust
+    /// let request = nano_get::Request::options_star("http://example.com").unwrap();
+    /// assert_eq!(request.request_line(), "OPTIONS * HTTP/1.1");
+    /// ```
+    pub fn options_star<A: ToUrl>(url: A) -> Result<Self, Box<dyn Error>> {
+        let mut request = Self::default_get_request(url)?;
+        request.request_type = RequestType::OPTIONS;
+        request.asterisk_target = true;
+        Ok(request)
+    }
+
     fn get_default_headers(url: &Url) -> HashMap<String, String> {
         let mut headers = HashMap::with_capacity(4);
-        headers.insert("user-agent".to_string(), "mini-get/0.1.0".to_string());
-        headers.insert("accept".to_string(), "*/*".to_string());
+        headers.insert("user-agent".to_string(), USER_AGENT.to_string());
+        headers.insert("accept".to_string(), DEFAULT_ACCEPT.to_string());
         headers.insert("host".to_string(), url.host.clone());
         headers.insert("connection".to_string(), "close".to_string());
         headers
@@ -178,7 +485,7 @@ impl Request {
     ///
     /// ## Example
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// use nano_get::Response;
     ///
     /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
@@ -187,13 +494,259 @@ impl Request {
     /// println!("{}", response.status);
     /// println!("{}", response.body);
     /// ```
+    ///
+    /// If the request has Digest credentials (see `digest_auth`) and the response is a 401 with a
+    /// Digest challenge, the request is sent once more with the answer to the challenge.
+    ///
+    /// Redirects are followed as configured by `follow_redirects` and `redirect_policy`.
     pub fn execute(&self) -> Result<Response, NanoGetError> {
+        // the overall timeout covers the redirects and the answer to a Digest challenge as well, so
+        // the deadline is fixed once, and passed on to every request that follows.
+        let mut request = self.clone();
+        request.deadline = self.get_deadline();
+        let mut response = request.execute_authenticated()?;
+        let mut redirected: Option<Request> = None;
+        for _ in 0..self.max_redirects {
+            let current = redirected.as_ref().unwrap_or(&request);
+            let next = match current.next_redirect(&response)? {
+                Some(next) => next,
+                None => break,
+            };
+            response = next.execute_authenticated()?;
+            redirected = Some(next);
+        }
+        self.check_status(&response.status)?;
+        Ok(response)
+    }
+
+    fn execute_authenticated(&self) -> Result<Response, NanoGetError> {
+        if self.digest_auth.is_none() {
+            return self.execute_once();
+        }
+        // the host is resolved once, so that the answer to the challenge is sent to the same addresses.
+        let mut request = self.clone();
+        if request.resolved.is_none() {
+            request.resolved = Some(resolve(&self.url)?);
+        }
+        let response = request.execute_once()?;
+        match request.answer_digest_challenge(&response) {
+            Some(request) => request.execute_once(),
+            None => Ok(response),
+        }
+    }
+
+    fn execute_once(&self) -> Result<Response, NanoGetError> {
+        #[cfg(feature = "https")] {
+            if self.is_https() {
+                return request_https_get(self);
+            }
+        }
+        request_http_get(self)
+    }
+
+    /// Returns the request for the next hop, if the response is a redirect that is allowed by the
+    /// redirect policy.
+    ///
+    /// The next request is a GET without a body. The credentials are dropped if the redirect leaves
+    /// the origin (protocol, host and port) of the current request.
+    ///
+    /// Fails with an error of kind `InsecureRedirect` if the request requires https and the
+    /// redirect is to any other protocol.
+    fn next_redirect(&self, response: &Response) -> Result<Option<Request>, NanoGetError> {
+        if !response.is_followable_redirect() {
+            return Ok(None);
+        }
+        let url = match response.location_url(&self.url) {
+            Some(Ok(url)) => url,
+            _ => return Ok(None),
+        };
+        if self.require_https && !url.protocol.eq_ignore_ascii_case("https") {
+            return Err(NanoGetError::new(ErrorKind::InsecureRedirect));
+        }
+        if let Some(RedirectPolicy(policy)) = self.redirect_policy.as_ref() {
+            if !policy(&self.url, &url) {
+                return Ok(None);
+            }
+        }
+        if let Some(OnRedirect(callback)) = self.on_redirect.as_ref() {
+            if !callback(response, &url) {
+                return Ok(None);
+            }
+        }
+        let preserves_body = matches!(response.get_status_code(), Some(307) | Some(308));
+        if preserves_body && self.body_reader.is_some() {
+            // the body was consumed while sending this request, so it can't be sent again.
+            return Ok(None);
+        }
+        let mut request = self.clone();
+        let same_origin = url.protocol.eq_ignore_ascii_case(&self.url.protocol)
+            && url.host.eq_ignore_ascii_case(&self.url.host) && url.port == self.url.port;
+        if !same_origin {
+            if let Some(headers) = request.headers.as_mut() {
+                headers.retain(|k, _| !REDACTED_HEADERS.iter().any(|h| h.eq_ignore_ascii_case(k)));
+            }
+            request.digest_auth = None;
+        }
+        request.add_header("host", &url.host);
+        request.url = url;
+        request.resolved = None;
+        if !preserves_body {
+            request.reset_body();
+        }
+        Ok(Some(request))
+    }
+
+    /// Returns the request to retry with, if the response is a Digest challenge that can be answered
+    /// with the credentials of this request.
+    fn answer_digest_challenge(&self, response: &Response) -> Option<Request> {
+        let credentials = self.digest_auth.as_ref()?;
+        if response.get_status_code() != Some(401) || self.body_reader.is_some() {
+            return None;
+        }
+        let challenge = response.auth_challenge()?;
+        let authorization = digest::authorization(&challenge, &credentials.username, &credentials.password,
+                                                  self.get_request_type(), &self.url.path,
+                                                  &digest::generate_cnonce(), 1)?;
+        let mut request = self.clone();
+        request.add_header("authorization", &authorization);
+        Some(request)
+    }
+
+    /// Executes the request like `execute`, reading the body of the response into the given buffer
+    /// instead of a new `Response`. Returns the status of the response.
+    ///
+    /// The buffer is cleared first, and then holds only the body. Reusing the same buffer for many
+    /// requests saves allocating a new one for each response.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// let mut body = Vec::with_capacity(4096);
+    /// for _ in 0..10 {
+    ///     let status = request.execute_into(&mut body).unwrap();
+    ///     println!("{}: {} bytes", status, body.len());
+    /// }
+    /// ```
+    pub fn execute_into(&self, buf: &mut Vec<u8>) -> Result<ResponseStatus, NanoGetError> {
+        let status = self.execute_into_once(buf)?;
+        self.check_status(&status)?;
+        Ok(status)
+    }
+
+    fn execute_into_once(&self, buf: &mut Vec<u8>) -> Result<ResponseStatus, NanoGetError> {
+        #[cfg(feature = "https")] {
+            if self.is_https() {
+                return request_https_get_into(self, buf);
+            }
+        }
+        request_http_get_into(self, buf)
+    }
+
+    /// Executes the request like `execute`, copying the body of the response to the writer as it is
+    /// read instead of buffering it. Returns the status of the response.
+    ///
+    /// This is the way to download bodies of any size, like files larger than the memory (or the
+    /// address space, on 32-bit targets). `execute` and `execute_into` fail with an error of kind
+    /// `BodyTooLarge` for a body that doesn't fit in memory.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let request = nano_get::Request::default_get_request("http://example.com/disk.img").unwrap();
+    /// let mut file = std::fs::File::create("disk.img").unwrap();
+    /// let status = request.execute_to_writer(&mut file).unwrap();
+    /// ```
+    pub fn execute_to_writer(&self, writer: &mut dyn Write) -> Result<ResponseStatus, NanoGetError> {
+        let status = self.execute_to_writer_once(writer)?;
+        self.check_status(&status)?;
+        Ok(status)
+    }
+
+    fn execute_to_writer_once(&self, writer: &mut dyn Write) -> Result<ResponseStatus, NanoGetError> {
+        #[cfg(feature = "https")] {
+            if self.is_https() {
+                return request_https_get_to_writer(self, writer);
+            }
+        }
+        request_http_get_to_writer(self, writer)
+    }
+
+    /// Executes the request like `execute`, returning an iterator over the lines of the body that
+    /// reads them off the connection as they arrive, instead of waiting for the whole response.
+    ///
+    /// This is meant for endpoints that stream records, like newline-delimited JSON over a chunked
+    /// response. A chunked body is decoded on the fly, so a line may span any number of chunks, and
+    /// only the current line is held in memory. The overall timeout only covers receiving the
+    /// head of the response, and redirects and Digest challenges are not handled.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let request = nano_get::Request::default_get_request("http://example.com/events").unwrap();
+    /// let lines = request.execute_lines().unwrap();
+    /// println!("{}", lines.status());
+    /// for line in lines {
+    ///     println!("record: {}", line.unwrap());
+    /// }
+    /// ```
+    pub fn execute_lines(&self) -> Result<ResponseLines, NanoGetError> {
+        let lines = self.execute_lines_once()?;
+        self.check_status(lines.status())?;
+        Ok(lines)
+    }
+
+    fn execute_lines_once(&self) -> Result<ResponseLines, NanoGetError> {
+        #[cfg(feature = "https")] {
+            if self.is_https() {
+                return request_https_lines(self);
+            }
+        }
+        request_http_lines(self)
+    }
+
+    /// Executes the request like `execute`, but only reads the status and headers of the response.
+    ///
+    /// The connection is closed right after the headers, abandoning the body unread, so the returned
+    /// `Response` has an empty body. This lets you check the status and headers (like the
+    /// `Content-Length` or `ETag`) of a large resource without downloading it. Unlike a HEAD request,
+    /// the server handles this as a regular GET, which matters for servers that answer HEAD
+    /// requests differently. Redirects and Digest challenges are not handled.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let request = nano_get::Request::default_get_request("http://example.com/big.iso").unwrap();
+    /// let response = request.execute_head_only().unwrap();
+    /// println!("{}: {:?}", response.status, response.header_values("content-length"));
+    /// ```
+    pub fn execute_head_only(&self) -> Result<Response, NanoGetError> {
+        let response = self.execute_head_only_once()?;
+        self.check_status(&response.status)?;
+        Ok(response)
+    }
+
+    fn execute_head_only_once(&self) -> Result<Response, NanoGetError> {
         #[cfg(feature = "https")] {
             if self.is_https() {
-                return request_https_get(&self);
+                return request_https_head_only(self);
             }
         }
-        request_http_get(&self)
+        request_http_head_only(self)
+    }
+
+    /// Opens a tunnel to the target (like `example.com:22`) through the HTTP proxy, using the
+    /// `CONNECT` method, and returns the connected stream for the caller to speak any protocol over.
+    ///
+    /// Fails with an error of kind `ProxyError` if the proxy does not answer with a 2xx status.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use std::io::Write;
+    /// use nano_get::{Request, Url};
+    ///
+    /// let proxy = Url::new("http://proxy.internal:3128");
+    /// let mut stream = Request::connect_tunnel(proxy, "git.example.com:22").unwrap();
+    /// stream.write_all(b"SSH-2.0-client\r\n").unwrap();
+    /// ```
+    pub fn connect_tunnel(proxy: Url, target: &str) -> Result<TcpStream, NanoGetError> {
+        connect_tunnel(&proxy, target)
     }
 
     /// Returns the headers as an Iterator over the key-value pairs.
@@ -216,36 +769,1328 @@ impl Request {
     }
 
     /// Convenience method to check if the request is a https request based
-    /// on the embedded url's protocol.
+    /// on the embedded url's protocol (which is case-insensitive).
     pub fn is_https(&self) -> bool {
-        self.url.protocol.as_str() == "https"
+        self.url.protocol.eq_ignore_ascii_case("https")
+    }
+
+    /// Convenience method to get the host of the embedded url, like `example.com` (or `[::1]` for
+    /// an IPv6 address).
+    pub fn host(&self) -> &str {
+        &self.url.host
+    }
+
+    /// Convenience method to get the port of the embedded url, which is the default port of the
+    /// protocol if the url has none.
+    ///
+    /// Returns 0 if the port is not a valid port number, which is only possible for a url that was
+    /// not validated (see `Url::parse`).
+    ///
+    /// ## Example
+    /// ```rust
+    /// let request = nano_get::Request::default_get_request("https://example.com/").unwrap();
+    /// assert_eq!((request.host(), request.port()), ("example.com", 443));
+    /// ```
+    pub fn port(&self) -> u16 {
+        self.url.port.parse().unwrap_or(0)
+    }
+
+    /// Overrides the value of the `Host` header, which is the host of the url by default.
+    ///
+    /// Only the header changes: the connection is still made to the host of the url (and with the
+    /// "https" feature, the certificate is still verified against it). This is useful for testing
+    /// virtual hosts on a server that is reached by its IP address. When a redirect is followed (see
+    /// `follow_redirects`), the `Host` header is set to the host of the redirect again.
+    ///
+    /// Like `Request::new`, this returns an error of kind `ParseError` if the value is not valid.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let mut request = nano_get::Request::default_get_request("http://203.0.113.7/").unwrap();
+    /// request.set_host_header("staging.example.com").unwrap();
+    /// let response = request.execute().unwrap();
+    /// ```
+    pub fn set_host_header(&mut self, host: &str) -> Result<(), NanoGetError> {
+        if !is_valid_header_value(host) {
+            return Err(NanoGetError::new(ErrorKind::ParseError));
+        }
+        self.add_header("host", host);
+        Ok(())
+    }
+
+    /// Replaces the path of the embedded url, keeping its query. See `Url::set_path`.
+    pub fn set_path(&mut self, path: &str) {
+        self.url.set_path(path);
+    }
+
+    /// Replaces the query of the embedded url with the given key-value pairs. See `Url::set_query`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// request.set_path("/api/users");
+    /// request.set_query(&[("name", "jane doe"), ("limit", "10")]);
+    /// assert_eq!(request.request_line(), "GET /api/users?name=jane%20doe&limit=10 HTTP/1.1");
+    /// ```
+    pub fn set_query(&mut self, pairs: &[(&str, &str)]) {
+        self.url.set_query(pairs);
     }
 
     /// Returns the type of HTTP Request.
     ///
-    /// Currently only returns `"GET"`. For Future Use.
+    /// This is `"GET"`, except for the requests made by `options_star`.
     pub fn get_request_type(&self) -> &str {
         self.request_type.value()
     }
 
+    /// Returns the request line exactly as it is sent, without the trailing CRLF (like
+    /// `GET /path?query HTTP/1.1`).
+    ///
+    /// Useful for signing requests, as HMAC based auth schemes often sign the method and target.
+    ///
+    /// ## Example
+    /// ```rust
+    /// let request = nano_get::Request::default_get_request("http://example.com/search?q=a%20b").unwrap();
+    /// assert_eq!(request.request_line(), "GET /search?q=a%20b HTTP/1.1");
+    /// ```
+    pub fn request_line(&self) -> String {
+        let target = if self.asterisk_target { "*" } else { &self.url.path };
+        format!("{} {} HTTP/1.1", self.get_request_type(), target)
+    }
+
     /// Add an additional header to the request.
     ///
     /// You can overwrite existing values by adding the header with the new value.
+    /// Header names are compared ignoring case.
     ///
     /// You cannot however remove the presence of a header.
     pub fn add_header(&mut self, key: &str, value: &str) {
-        if self.headers.is_some() {
-            self.headers.as_mut().unwrap().insert((*key).to_string(), (*value).to_string());
+        if let Some(headers) = self.headers.as_mut() {
+            headers.retain(|k, _| !k.eq_ignore_ascii_case(key));
+            headers.insert((*key).to_string(), (*value).to_string());
         } else {
             let mut headers = HashMap::new();
             headers.insert((*key).to_string(), (*value).to_string());
             self.headers = Some(headers);
         }
     }
-}
 
-fn process_headers(headers: Option<Vec<Header>>) -> Option<HashMap<String, String>> {
-    headers.map(|vec| {
-        vec.iter().cloned().map(|(k, v)| (k.to_string(), v.to_string())).collect()
-    })
-}
\ No newline at end of file
+    /// Removes the header with the given name (ignoring case) from the request, if present.
+    ///
+    /// This also removes the default headers, like the `connection: close` header, for servers
+    /// that misbehave when they see it. Without a `Connection` header, the body of a response with
+    /// no declared length is read until the server closes the connection.
+    ///
+    /// ## Example
+    /// ```rust
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// request.remove_header("Connection");
+    /// assert!(!request.get_request_headers().any(|(k, _)| k == "connection"));
+    /// ```
+    pub fn remove_header(&mut self, key: &str) {
+        if let Some(headers) = self.headers.as_mut() {
+            headers.retain(|k, _| !k.eq_ignore_ascii_case(key));
+        }
+    }
+
+    /// Adds all the given headers to the request at once.
+    ///
+    /// Like `add_header`, any existing header with the same name (ignoring case) is overwritten.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    ///
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// let mut captured = HashMap::new();
+    /// captured.insert("Accept".to_string(), "application/json".to_string());
+    /// captured.insert("X-Trace".to_string(), "abc".to_string());
+    /// request.extend_headers(captured);
+    /// ```
+    pub fn extend_headers<I: IntoIterator<Item=(String, String)>>(&mut self, headers: I) {
+        for (k, v) in headers {
+            self.add_header(&k, &v);
+        }
+    }
+
+    /// Removes the hop-by-hop headers from the request.
+    ///
+    /// These are the headers that only apply to a single connection (`Connection`, `Keep-Alive`,
+    /// `Proxy-Authenticate`, `Proxy-Authorization`, `TE`, `Trailer`, `Transfer-Encoding` & `Upgrade`),
+    /// along with any header named in the `Connection` header. They must not be copied along when
+    /// forwarding a request, for example when building a proxy on top of this crate.
+    /// End-to-end headers, like `X-Forwarded-For`, are kept.
+    ///
+    /// The crate still manages its own connection, so the default `connection: close` header is
+    /// put back after the headers are removed.
+    ///
+    /// ## Example
+    /// ```rust
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// request.add_header("x-forwarded-for", "203.0.113.7");
+    /// request.add_header("Connection", "keep-alive, x-hop");
+    /// request.add_header("x-hop", "1");
+    /// request.add_header("Upgrade", "websocket");
+    /// request.remove_hop_by_hop_headers();
+    /// let headers: Vec<(&str, &str)> = request.get_request_headers().collect();
+    /// assert!(headers.contains(&("x-forwarded-for", "203.0.113.7")));
+    /// assert!(headers.contains(&("connection", "close")));
+    /// assert!(!headers.iter().any(|(k, _)| *k == "x-hop" || *k == "Upgrade"));
+    /// ```
+    pub fn remove_hop_by_hop_headers(&mut self) {
+        if let Some(headers) = self.headers.as_mut() {
+            let connection: Vec<&str> = headers.iter()
+                .filter(|(k, _)| k.eq_ignore_ascii_case("connection"))
+                .map(|(_, v)| v.as_str())
+                .collect();
+            let connection = connection.join(",");
+            headers.retain(|k, _| !is_hop_by_hop_header(k, Some(&connection)));
+        }
+        self.add_header("connection", "close");
+    }
+
+    /// Set the body of the request to be read from the given reader while the request is sent,
+    /// instead of being buffered in memory.
+    ///
+    /// If the length of the body is known, it is sent as the `Content-Length` and exactly that many
+    /// bytes are copied from the reader. Otherwise, the body is sent using chunked transfer encoding.
+    ///
+    /// This takes precedence over the `body` field. The reader is consumed the first time the
+    /// request is sent.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let file = std::fs::File::open("upload.bin").unwrap();
+    /// let length = file.metadata().unwrap().len();
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/upload").unwrap();
+    /// request.body_from_reader(file, Some(length));
+    /// ```
+    pub fn body_from_reader<R: Read + Send + 'static>(&mut self, reader: R, known_len: Option<u64>) {
+        self.body_reader = Some(BodyReader {
+            reader: Arc::new(Mutex::new(Box::new(reader))),
+            length: known_len,
+        });
+    }
+
+    /// Removes the body of the request, whether from the `body` field, `set_body_slice` or
+    /// `body_from_reader`, along with its `Content-Length` and `Content-Type` headers, and resets
+    /// the method to GET (with the path of the url as the target, for an `options_star` request).
+    ///
+    /// Useful for reusing a clone of a request that has a body for one that must not. This is what
+    /// happens to the request when following a 301, 302 or 303 redirect (see `follow_redirects`).
+    ///
+    /// ## Example
+    /// ```rust
+    /// let mut request = nano_get::Request::new("http://example.com/", None, Some("a=1".to_string())).unwrap();
+    /// request.add_header("Content-Type", "application/x-www-form-urlencoded");
+    /// request.reset_body();
+    /// assert_eq!(request.body, None);
+    /// assert_eq!(request.request_line(), "GET / HTTP/1.1");
+    /// assert!(!request.get_request_headers().any(|(k, _)| k.eq_ignore_ascii_case("content-type")));
+    /// ```
+    pub fn reset_body(&mut self) {
+        self.body = None;
+        self.body_reader = None;
+        self.body_bytes = None;
+        self.request_type = RequestType::GET;
+        self.asterisk_target = false;
+        if let Some(headers) = self.headers.as_mut() {
+            headers.retain(|k, _| !k.eq_ignore_ascii_case("content-length") && !k.eq_ignore_ascii_case("content-type"));
+        }
+    }
+
+    /// Set the body of the request to a copy of the given bytes, which need not be valid UTF-8.
+    ///
+    /// The bytes are written to the connection as they are, with a `Content-Length`. This takes
+    /// precedence over the `body` field, while a body set with `body_from_reader` takes precedence
+    /// over this.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/upload").unwrap();
+    /// request.set_body_slice(&[0x89, b'P', b'N', b'G']);
+    /// request.add_header("Content-Type", "image/png");
+    /// ```
+    pub fn set_body_slice(&mut self, body: &[u8]) {
+        self.body_bytes = Some(body.to_vec());
+    }
+
+    /// Set the body of the request to the given `multipart/form-data` form, along with the
+    /// `Content-Type` header that declares its boundary.
+    ///
+    /// The form is sent with a `Content-Length`, like a body set with `set_body_slice`.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use nano_get::{MultipartBuilder, Request};
+    ///
+    /// let mut builder = MultipartBuilder::new();
+    /// builder.add_file("report", "report.csv", "text/csv", b"a,b\n1,2\n");
+    /// let mut request = Request::default_get_request("http://example.com/upload").unwrap();
+    /// request.multipart_body(builder.build());
+    /// ```
+    pub fn multipart_body(&mut self, form: MultipartForm) {
+        self.add_header("Content-Type", &form.content_type());
+        self.body_bytes = Some(form.body);
+    }
+
+    /// Returns the body to send, from `set_body_slice` or else the `body` field. The body from a
+    /// reader is not included.
+    pub(crate) fn get_body_bytes(&self) -> Option<&[u8]> {
+        self.body_bytes.as_deref().or_else(|| self.body.as_ref().map(String::as_bytes))
+    }
+
+    pub(crate) fn get_body_reader(&self) -> Option<&BodyReader> {
+        self.body_reader.as_ref()
+    }
+
+    /// Set whether a body from `body_from_reader` waits for the server to accept it.
+    ///
+    /// When turned on, the request is sent with `Expect: 100-continue`, in this sequence:
+    /// 1. The request line and headers are sent, without the body.
+    /// 2. The server answers with an interim `100 Continue`, and the body is then sent (chunked, if
+    ///    its length is unknown), followed by reading the final response.
+    /// 3. If the server answers with a final response instead (like `401` or `413`), the body is
+    ///    never sent and that response is returned.
+    ///
+    /// If the server does not answer within the read timeout of the request, the body is sent
+    /// anyway, as servers that don't support `Expect` never answer. Without a read timeout, the
+    /// request waits for the server to answer.
+    ///
+    /// This saves uploading large bodies that the server would reject. It has no effect on a
+    /// request without a body from a reader. By default, the body is sent right away.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// let file = std::fs::File::open("upload.bin").unwrap();
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/upload").unwrap();
+    /// request.body_from_reader(file, None);
+    /// request.expect_continue(true);
+    /// request.read_timeout(Duration::from_secs(1));
+    /// let response = request.execute().unwrap();
+    /// ```
+    pub fn expect_continue(&mut self, expect: bool) {
+        self.expect_continue = expect;
+    }
+
+    pub(crate) fn get_expect_continue(&self) -> bool {
+        self.expect_continue && self.body_reader.is_some()
+    }
+
+    /// Set whether the interim (1xx) responses sent before the final response are kept, to be read
+    /// from `Response::informational`.
+    ///
+    /// Interim responses, like `100 Continue` or `103 Early Hints`, are always skipped over. By
+    /// default, they are also discarded, so `Response::informational` is empty.
+    pub fn collect_informational(&mut self, collect: bool) {
+        self.collect_informational = collect;
+    }
+
+    pub(crate) fn get_collect_informational(&self) -> bool {
+        self.collect_informational
+    }
+
+    /// Set whether the exact bytes sent and received are kept, to be read from
+    /// `Response::raw_exchange`, like for recording responses to replay in tests.
+    ///
+    /// This is off by default, as it keeps a second copy of the whole exchange (including the body)
+    /// in memory. The capture is only made by `execute` and `execute_head_only`. For https, the bytes
+    /// are those inside the TLS connection.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// request.capture_exchange(true);
+    /// let response = request.execute().unwrap();
+    /// let exchange = response.raw_exchange().unwrap();
+    /// std::fs::write("request.http", &exchange.request).unwrap();
+    /// std::fs::write("response.http", &exchange.response).unwrap();
+    /// ```
+    pub fn capture_exchange(&mut self, capture: bool) {
+        self.capture_exchange = capture;
+    }
+
+    pub(crate) fn get_capture_exchange(&self) -> bool {
+        self.capture_exchange
+    }
+
+    /// Set the status codes that are acceptable for the response. Executing the request fails with
+    /// an error of kind `HttpStatus`, carrying the actual code, for a response with any other status.
+    ///
+    /// The status is checked on the final response, after any redirects are followed, by all the
+    /// ways of executing the request. The body is still read first (except by `execute_lines` and
+    /// `execute_head_only`). An empty list removes the expectation, which is the default: any
+    /// status is returned as a successful `Response`.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use nano_get::ErrorKind;
+    ///
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/items").unwrap();
+    /// request.expect_status(&[200, 201]);
+    /// match request.execute() {
+    ///     Ok(response) => println!("{}", response.body),
+    ///     Err(err) => match err.kind() {
+    ///         ErrorKind::HttpStatus(code) => println!("unexpected status {}", code),
+    ///         _ => println!("failed: {}", err),
+    ///     },
+    /// }
+    /// ```
+    pub fn expect_status(&mut self, codes: &[u16]) {
+        self.expected_statuses = Some(codes.to_vec()).filter(|codes| !codes.is_empty());
+    }
+
+    /// Fails with an error of kind `HttpStatus` if the status is not one of the expected statuses.
+    fn check_status(&self, status: &ResponseStatus) -> Result<(), NanoGetError> {
+        let expected = match self.expected_statuses.as_ref() {
+            Some(expected) => expected,
+            None => return Ok(()),
+        };
+        let code = status.0.get_code();
+        if code.is_some_and(|code| expected.contains(&code)) {
+            return Ok(());
+        }
+        let code = code.unwrap_or(0);
+        Err(NanoGetError::with_source(ErrorKind::HttpStatus(code),
+                                      format!("unexpected status {}, expected one of {:?}", code, expected)))
+    }
+
+    /// Set the credentials to answer a Digest authentication challenge with, as per
+    /// [RFC 7616](https://tools.ietf.org/html/rfc7616).
+    ///
+    /// If the server responds with a 401 and a Digest challenge in its `WWW-Authenticate` header,
+    /// `execute` computes the answer to the challenge and sends the request once more with it in
+    /// the `Authorization` header. The `MD5` and `SHA-256` algorithms (and their `-sess` variants)
+    /// are supported, with `qop=auth`. A request with a body from `body_from_reader` is not sent
+    /// again, as its body has already been consumed. `execute_into` does not answer challenges.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let mut request = nano_get::Request::default_get_request("http://192.168.1.1/status").unwrap();
+    /// request.digest_auth("admin", "secret");
+    /// let response = request.execute().unwrap();
+    /// ```
+    pub fn digest_auth(&mut self, username: &str, password: &str) {
+        self.digest_auth = Some(DigestCredentials { username: username.to_string(), password: password.to_string() });
+    }
+
+    /// Follow up to `max` redirects (301, 302, 303, 307 and 308 responses with a `Location`) when
+    /// the request is executed.
+    ///
+    /// Each redirect is followed to the `Location` resolved against the current url. A 307 or 308
+    /// redirect is sent with the same method and body, while the others are followed with a GET
+    /// request without a body (see `reset_body`). A 307 or 308 redirect of a request with a body
+    /// from `body_from_reader` is not followed, as its body has already been consumed, and the
+    /// redirect response is returned instead. The `Authorization`, `Proxy-Authorization` and `Cookie` headers are
+    /// only sent again if the redirect stays on the same protocol, host and port. Once `max`
+    /// redirects have been followed, the last redirect response is returned as-is.
+    ///
+    /// By default, redirects are not followed. Use `Response::follow` to follow them by hand.
+    pub fn follow_redirects(&mut self, max: usize) {
+        self.max_redirects = max;
+    }
+
+    /// Set whether every redirect that is followed must stay on https.
+    ///
+    /// When turned on, a redirect to any other protocol (like a downgrade from https to http) fails
+    /// the request with an error of kind `InsecureRedirect`, instead of being followed. This guards
+    /// against TLS stripping through a malicious redirect. It only applies when redirects are
+    /// followed (see `follow_redirects`).
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let mut request = nano_get::Request::default_get_request("https://example.com/").unwrap();
+    /// request.follow_redirects(5);
+    /// request.require_https(true);
+    /// let response = request.execute().unwrap();
+    /// ```
+    pub fn require_https(&mut self, require: bool) {
+        self.require_https = require;
+    }
+
+    /// Set a policy that decides whether each redirect is followed, given the current and the next url.
+    ///
+    /// When the policy returns `false`, following stops and the redirect response is returned
+    /// as-is, rather than an error. This only applies when redirects are followed (see
+    /// `follow_redirects`), and is useful for guarding against SSRF, for example by only allowing
+    /// redirects to the same host.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// request.follow_redirects(5);
+    /// request.redirect_policy(Box::new(|current, next| current.host == next.host));
+    /// let response = request.execute().unwrap();
+    /// if response.status.0.is_redirect() {
+    ///     println!("refused to follow the redirect to {:?}", response.location());
+    /// }
+    /// ```
+    pub fn redirect_policy(&mut self, f: Box<RedirectHook>) {
+        self.redirect_policy = Some(RedirectPolicy(Arc::from(f)));
+    }
+
+    /// Set a callback that is given each redirect response, along with the url it redirects to
+    /// (resolved against the current url), before the redirect is followed.
+    ///
+    /// This gives access to the whole response of each hop, for example to log the chain of
+    /// redirects or to capture the `Set-Cookie` headers of the intermediate responses. When the
+    /// callback returns `false`, following stops and the current 3xx response is returned, rather
+    /// than an error. It is only called for redirects that are allowed by the `redirect_policy`,
+    /// and only when redirects are followed (see `follow_redirects`).
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// request.follow_redirects(5);
+    /// request.on_redirect(Box::new(|response, next| {
+    ///     println!("{} -> {}", response.status, next.as_absolute());
+    ///     true
+    /// }));
+    /// let response = request.execute().unwrap();
+    /// ```
+    pub fn on_redirect(&mut self, f: Box<RedirectResponseHook>) {
+        self.on_redirect = Some(OnRedirect(Arc::from(f)));
+    }
+
+    /// Set whether to refuse connecting to private addresses.
+    ///
+    /// When turned on, the addresses the host resolves to are checked before connecting, and the
+    /// request fails with an error of kind `BlockedAddress` if any of them is a loopback, link-local,
+    /// private (RFC 1918), unique-local or unspecified address. This guards servers that fetch
+    /// user-supplied urls against SSRF. The check is repeated for every redirect that is followed,
+    /// after resolving its host again.
+    ///
+    /// By default, all addresses are allowed.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::ErrorKind;
+    ///
+    /// let mut request = nano_get::Request::default_get_request("http://127.0.0.1:8080/admin").unwrap();
+    /// request.block_private_addresses(true);
+    /// assert_eq!(request.execute().unwrap_err().kind(), &ErrorKind::BlockedAddress);
+    /// ```
+    pub fn block_private_addresses(&mut self, block: bool) {
+        self.block_private_addresses = block;
+    }
+
+    pub(crate) fn blocks_private_addresses(&self) -> bool {
+        self.block_private_addresses
+    }
+
+    /// Set a hook that can change the headers right before the request is sent.
+    ///
+    /// The hook is given the final headers, after the default and custom headers are put together,
+    /// and runs every time the request is sent (over both http and https). Any change it makes
+    /// only applies to what is sent, and not to the request itself. This is useful for headers that
+    /// depend on all the others, like a signature, or on the time of sending, like a timestamp.
+    ///
+    /// The `Content-Length` or `Transfer-Encoding` of the body is added after the hook runs,
+    /// unless the hook sets it itself.
+    ///
+    /// ## Example
+    /// ```rust
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// request.before_send(Box::new(|headers| {
+    ///     let mut names: Vec<&String> = headers.keys().collect();
+    ///     names.sort();
+    ///     let signed = names.iter().map(|name| name.to_lowercase()).collect::<Vec<_>>().join(";");
+    ///     headers.insert("x-signed-headers".to_string(), signed);
+    /// }));
+    /// ```
+    pub fn before_send(&mut self, f: Box<HeaderHook>) {
+        self.before_send = Some(BeforeSend(Arc::from(f)));
+    }
+
+    /// Returns the complete set of headers, exactly as they are sent on the wire.
+    ///
+    /// Unlike `get_request_headers`, this includes the headers that are only worked out when the
+    /// request is sent: the changes of the `before_send` hook, the `Content-Length` (or
+    /// `Transfer-Encoding: chunked`) of the body, and the `Expect` header of `expect_continue`.
+    /// Useful for debugging why a server rejects a request.
+    ///
+    /// ## Example
+    /// ```rust
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// request.body = Some("Hello World!".to_string());
+    /// let headers = request.effective_headers();
+    /// assert!(headers.contains(&("content-length".to_string(), "12".to_string())));
+    /// ```
+    pub fn effective_headers(&self) -> Vec<(String, String)> {
+        let mut headers = self.headers.clone().unwrap_or_default();
+        if let Some(BeforeSend(hook)) = self.before_send.as_ref() {
+            hook(&mut headers);
+        }
+        let framing = self.get_body_framing_header(&headers);
+        let expects = self.get_expect_continue() && !headers.keys().any(|k| k.eq_ignore_ascii_case("expect"));
+        let mut headers: Vec<(String, String)> = headers.into_iter().collect();
+        headers.extend(framing);
+        if expects {
+            headers.push(("expect".to_string(), "100-continue".to_string()));
+        }
+        headers
+    }
+
+    /// Returns the header that declares how the body of the request is delimited, unless the given
+    /// headers already set it.
+    fn get_body_framing_header(&self, headers: &HashMap<String, String>) -> Option<(String, String)> {
+        let framed = headers.keys()
+            .any(|k| k.eq_ignore_ascii_case("content-length") || k.eq_ignore_ascii_case("transfer-encoding"));
+        if framed {
+            return None;
+        }
+        match self.body_reader.as_ref() {
+            Some(BodyReader { length: Some(length), .. }) => Some(("content-length".to_string(), length.to_string())),
+            Some(BodyReader { length: None, .. }) => Some(("transfer-encoding".to_string(), "chunked".to_string())),
+            None => self.get_body_bytes().map(|body| ("content-length".to_string(), body.len().to_string())),
+        }
+    }
+
+    /// Retry the TCP connect up to `retries` times, waiting `delay` between attempts, if the
+    /// connection is refused (`ECONNREFUSED`).
+    ///
+    /// This is meant for servers that are still starting up. Only the connect phase is retried,
+    /// nothing is ever re-sent. Any other connect failure is returned immediately. The host is
+    /// resolved once, and every attempt tries the same addresses in the same order, moving on to
+    /// the next address only when one fails to connect.
+    ///
+    /// By default, the connect is not retried.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let mut request = nano_get::Request::default_get_request("http://127.0.0.1:3000/").unwrap();
+    /// request.connect_retries(5, Duration::from_millis(200));
+    /// ```
+    pub fn connect_retries(&mut self, retries: u32, delay: Duration) {
+        self.connect_retries = retries;
+        self.connect_retry_delay = delay;
+    }
+
+    /// Set the low-level options of the socket to connect with, like `TCP_NODELAY`. See
+    /// `SocketConfig`.
+    ///
+    /// By default, the operating system's defaults are used. When executed through a `Client`, the
+    /// options of the `Client` are used for any option that is not set on the request.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::{Request, SocketConfig};
+    ///
+    /// let mut config = SocketConfig::default();
+    /// config.nodelay(true);
+    /// let mut request = Request::default_get_request("http://example.com/").unwrap();
+    /// request.socket_config(config);
+    /// ```
+    pub fn socket_config(&mut self, config: SocketConfig) {
+        self.socket_config = config;
+    }
+
+    /// Returns the addresses the host was already resolved to, if any.
+    pub(crate) fn get_resolved(&self) -> Option<&[SocketAddr]> {
+        self.resolved.as_deref()
+    }
+
+    pub(crate) fn get_connect_retries(&self) -> (u32, Duration) {
+        (self.connect_retries, self.connect_retry_delay)
+    }
+
+    /// Limit the total time allowed for reading the response body.
+    ///
+    /// This guards against servers that trickle the body slowly enough to never trip a per-read
+    /// timeout. Once the budget is used up, the request fails with an error of kind `Timeout`.
+    /// The budget only covers the body, starting after the response headers have been read.
+    ///
+    /// By default, there is no budget.
+    pub fn body_read_budget(&mut self, budget: Duration) {
+        self.body_read_budget = Some(budget);
+    }
+
+    pub(crate) fn get_body_read_budget(&self) -> Option<Duration> {
+        self.body_read_budget
+    }
+
+    /// Set a flag that cancels the request when it is set to `true`, e.g. from another thread.
+    ///
+    /// The flag is checked before sending the request and between reads of the response. Once
+    /// it is set, the request fails with an error of kind `Cancelled` and the connection is dropped.
+    /// A read that is already blocked waiting on the server is not interrupted.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    ///
+    /// let cancel = Arc::new(AtomicBool::new(false));
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// request.cancel_flag(Arc::clone(&cancel));
+    /// // later, from the UI thread:
+    /// cancel.store(true, Ordering::SeqCst);
+    /// ```
+    pub fn cancel_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.cancel_flag = Some(flag);
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel_flag.as_ref().is_some_and(|flag| flag.load(Ordering::SeqCst))
+    }
+
+    /// Set whether the response is parsed strictly.
+    ///
+    /// By default, the response is parsed on a best-effort basis, and lines of the head ending with a
+    /// bare `\n` are accepted. In strict mode, the request fails with an error of kind `ParseError`
+    /// if the head of the response is not valid UTF-8, has a line not ending with `\r\n`, a
+    /// malformed status line or a header line without a colon, if the length of the body is
+    /// ambiguous (both `Content-Length` and `Transfer-Encoding`, or conflicting `Content-Length`s),
+    /// or if the server sends anything after the end of the body on a connection that is not kept
+    /// alive (as with the default `connection: close`).
+    pub fn strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub(crate) fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Set whether the body is sent in the same write as the request line and headers.
+    ///
+    /// By default, the body is added to the write buffer (see `write_buffer_size`) after the headers,
+    /// so a small request is sent with a single write, which saves syscalls and packets. Turning this
+    /// off sends the body in a write of its own. A body set with `body_from_reader` is always
+    /// streamed after the headers.
+    pub fn coalesce_writes(&mut self, coalesce: bool) {
+        self.coalesce_writes = coalesce;
+    }
+
+    pub(crate) fn get_coalesce_writes(&self) -> bool {
+        self.coalesce_writes
+    }
+
+    /// Set the size of the buffer the request is written through.
+    ///
+    /// The request line and each of the headers are added to the buffer, which is only written to
+    /// the connection once full or once the whole request is in it. A request whose head fits in
+    /// the buffer is sent with a single write, no matter how many headers it has. A body from
+    /// `body_from_reader` is streamed through the same buffer, so each chunk of a chunked body
+    /// takes a single write as well.
+    ///
+    /// The default size is 8 KiB. Raise it for requests with unusually large sets of headers, like
+    /// dozens of cookies.
+    ///
+    /// ## Example
+    /// ```rust
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// request.write_buffer_size(64 * 1024);
+    /// ```
+    pub fn write_buffer_size(&mut self, size: usize) {
+        self.write_buffer_size = size;
+    }
+
+    /// Checks if the `accept` header is still the built-in default of the request.
+    pub(crate) fn has_default_accept(&self) -> bool {
+        self.headers.as_ref().is_some_and(|headers| headers.get("accept").map(String::as_str) == Some(DEFAULT_ACCEPT))
+    }
+
+    pub(crate) fn get_write_buffer_size(&self) -> usize {
+        self.write_buffer_size
+    }
+
+    /// Limit the number of headers read from the response (and, separately, the number of trailers).
+    ///
+    /// This guards against a server sending an endless stream of headers. Once the limit is
+    /// exceeded, the request fails with an error of kind `ParseError`.
+    ///
+    /// The default limit is 100 headers.
+    pub fn max_headers(&mut self, max: usize) {
+        self.max_headers = max;
+    }
+
+    pub(crate) fn get_max_headers(&self) -> usize {
+        self.max_headers
+    }
+
+    /// Limit the total number of bytes read for the response: the status line, headers, body (as
+    /// sent, so including any chunked framing) and trailers, along with any interim responses.
+    ///
+    /// This puts a single bound on the resources a response can take, which is simpler to reason
+    /// about than separate limits, like when forwarding responses. Once more bytes would be read,
+    /// the request fails with an error of kind `BodyTooLarge`, even if only the headers exceed it.
+    /// The other limits, like `max_headers`, still apply, and whichever is exceeded first fails the
+    /// request.
+    ///
+    /// By default, there is no limit.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// request.max_response_bytes(1024 * 1024);
+    /// let response = request.execute().unwrap();
+    /// ```
+    pub fn max_response_bytes(&mut self, max: u64) {
+        self.max_response_bytes = Some(max);
+    }
+
+    pub(crate) fn get_max_response_bytes(&self) -> Option<u64> {
+        self.max_response_bytes
+    }
+
+    /// Limit the rate at which the body of the response is read, in bytes per second.
+    ///
+    /// This is approximate: the body is read in pieces of at most one second's worth of bytes,
+    /// sleeping after each one for as long as it takes for the average rate to come down to the
+    /// limit. A small response may still arrive in a single read from the operating system's
+    /// buffers, and the head of the response is not limited. The sleeps count towards the overall
+    /// `timeout`. This does not apply to `execute_lines`, where the caller controls the pace.
+    ///
+    /// Useful for testing slow clients, or for crawling politely. A rate of 0 removes the limit,
+    /// which is the default.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/large").unwrap();
+    /// request.max_download_rate(64 * 1024);
+    /// let response = request.execute().unwrap();
+    /// ```
+    pub fn max_download_rate(&mut self, bytes_per_sec: u64) {
+        self.max_download_rate = Some(bytes_per_sec).filter(|rate| *rate > 0);
+    }
+
+    pub(crate) fn get_max_download_rate(&self) -> Option<u64> {
+        self.max_download_rate
+    }
+
+    /// Set whether the body of the response is decoded as text into `Response::body`.
+    ///
+    /// With the default `BodyMode::Auto`, the body is decoded if the `Content-Type` of the response
+    /// is missing, `text/*`, `application/json`, `application/xml`, `application/javascript`,
+    /// `application/x-www-form-urlencoded`, or ends in `+json` or `+xml` (like `image/svg+xml`).
+    /// Any other body is binary: `Response::body` is left empty, so that the bytes aren't corrupted
+    /// by decoding, and the body is only available from `Response::body_bytes`.
+    ///
+    /// `BodyMode::Text` and `BodyMode::Bytes` always or never decode the body respectively.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::{BodyMode, Request};
+    /// let mut request = Request::default_get_request("http://example.com/logo").unwrap();
+    /// request.body_mode(BodyMode::Bytes);
+    /// ```
+    pub fn body_mode(&mut self, mode: BodyMode) {
+        self.body_mode = mode;
+    }
+
+    pub(crate) fn get_body_mode(&self) -> BodyMode {
+        self.body_mode
+    }
+
+    /// Set the timeout for establishing the TCP connection.
+    ///
+    /// This is a budget for the connect as a whole, shared by all the addresses the host resolves
+    /// to: each address is given the time that is left after trying the ones before it. If the
+    /// timeout elapses, the request fails with an error of kind `Timeout`.
+    pub fn connect_timeout(&mut self, timeout: Duration) {
+        self.timeouts.connect = Some(timeout);
+    }
+
+    /// Set the timeout for each read from (and write to) the connection.
+    ///
+    /// If the timeout elapses, the request fails with an error of kind `Timeout`.
+    pub fn read_timeout(&mut self, timeout: Duration) {
+        self.timeouts.read = Some(timeout);
+    }
+
+    /// Set the overall timeout of the request, covering the connect, sending the request and
+    /// reading the response. With `execute`, it also covers any redirects that are followed and
+    /// the answer to a Digest challenge.
+    ///
+    /// If the timeout elapses, the request fails with an error of kind `Timeout`.
+    ///
+    /// By default, requests have no timeouts. When executed through a `Client`, the timeouts of
+    /// the `Client` are used for any timeout that is not set on the request.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// request.connect_timeout(Duration::from_secs(2));
+    /// request.timeout(Duration::from_secs(10));
+    /// ```
+    pub fn timeout(&mut self, timeout: Duration) {
+        self.timeouts.overall = Some(timeout);
+    }
+
+    /// Returns the point in time by which the request must complete, if it has an overall timeout.
+    ///
+    /// This is counted from now, unless the request is part of an `execute` that already fixed it.
+    pub(crate) fn get_deadline(&self) -> Option<Instant> {
+        self.deadline.or_else(|| self.timeouts.overall.map(|timeout| Instant::now() + timeout))
+    }
+}
+
+fn process_headers(headers: Option<Vec<Header>>) -> Option<HashMap<String, String>> {
+    headers.map(|vec| {
+        vec.iter().cloned().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    })
+}
+
+/// Checks if the header only applies to a single connection, given the value of the `Connection`
+/// header (which can name additional hop-by-hop headers).
+pub(crate) fn is_hop_by_hop_header(name: &str, connection: Option<&str>) -> bool {
+    const HOP_BY_HOP_HEADERS: [&str; 8] = ["connection", "keep-alive", "proxy-authenticate", "proxy-authorization",
+        "te", "trailer", "transfer-encoding", "upgrade"];
+    HOP_BY_HOP_HEADERS.iter().any(|h| h.eq_ignore_ascii_case(name))
+        || connection.is_some_and(|value| value.split(',').any(|h| h.trim().eq_ignore_ascii_case(name)))
+}
+
+pub(crate) fn validate_headers(headers: Option<&Vec<Header>>) -> Result<(), NanoGetError> {
+    let valid = |&(k, v): &Header| is_valid_header_name(k) && is_valid_header_value(v);
+    if headers.is_none_or(|vec| vec.iter().all(valid)) {
+        Ok(())
+    } else {
+        Err(NanoGetError::new(ErrorKind::ParseError))
+    }
+}
+
+fn is_valid_header_name(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+fn is_valid_header_value(value: &str) -> bool {
+    value.bytes().all(|b| b == b'\t' || (b' '..=b'~').contains(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StatusCode;
+    use crate::testing::{serve, serve_with};
+
+    #[test]
+    fn test_display_redacts_secrets() {
+        let headers = vec![("Cookie", "session=abc"), ("Proxy-Authorization", "Basic xyz"), ("x-trace", "1")];
+        let request = Request::new("http://example.com/a?b=c", Some(headers), None).unwrap();
+        assert_eq!(request.to_string(), "GET http://example.com:80/a?b=c (7 headers)");
+        let detailed = format!("{:#}", request);
+        assert!(detailed.contains("\nCookie: <redacted>"));
+        assert!(detailed.contains("\nProxy-Authorization: <redacted>"));
+        assert!(detailed.contains("\nx-trace: 1"));
+        assert!(!detailed.contains("abc") && !detailed.contains("xyz"));
+    }
+
+    #[test]
+    fn test_default_user_agent() {
+        let request = Request::default_get_request("http://example.com").unwrap();
+        let user_agent = request.get_request_headers().find(|(k, _)| *k == "user-agent").unwrap().1;
+        assert_eq!(user_agent, format!("nano-get/{}", env!("CARGO_PKG_VERSION")));
+    }
+
+    #[test]
+    fn test_extend_headers() {
+        let mut request = Request::default_get_request("http://example.com").unwrap();
+        let mut captured = HashMap::new();
+        captured.insert("Accept".to_string(), "text/html".to_string());
+        captured.insert("X-Trace".to_string(), "abc".to_string());
+        request.extend_headers(captured);
+        let mut headers: Vec<(&str, &str)> = request.get_request_headers().collect();
+        headers.sort();
+        assert_eq!(headers, vec![("Accept", "text/html"), ("X-Trace", "abc"), ("connection", "close"),
+                                 ("host", "example.com"), ("user-agent", USER_AGENT)]);
+    }
+
+    #[test]
+    fn test_effective_headers() {
+        let mut request = Request::default_get_request("http://example.com").unwrap();
+        request.body_from_reader(std::io::empty(), None);
+        request.before_send(Box::new(|headers| {
+            headers.insert("x-signature".to_string(), "sig".to_string());
+        }));
+        let mut headers = request.effective_headers();
+        headers.sort();
+        let names: Vec<&str> = headers.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(names, vec!["accept", "connection", "host", "transfer-encoding", "user-agent", "x-signature"]);
+        request.before_send(Box::new(|headers| {
+            headers.insert("Content-Length".to_string(), "0".to_string());
+        }));
+        let headers = request.effective_headers();
+        assert!(!headers.iter().any(|(k, _)| k == "transfer-encoding"));
+        assert!(headers.contains(&("Content-Length".to_string(), "0".to_string())));
+    }
+
+    #[test]
+    fn test_digest_auth_retries_with_answer() {
+        let (port, server) = serve(vec![
+            b"HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Digest realm=\"cam\", qop=\"auth\", nonce=\"n1\"\r\n\
+              Content-Length: 0\r\n\r\n",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+        ]);
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/status", port)).unwrap();
+        request.digest_auth("admin", "secret");
+        let response = request.execute().unwrap();
+        assert_eq!(response.body, "ok");
+        let heads = server.join().unwrap();
+        assert!(!heads[0].to_ascii_lowercase().contains("authorization"));
+        assert!(heads[1].contains("authorization: Digest username=\"admin\", realm=\"cam\", nonce=\"n1\", \
+            uri=\"/status\", algorithm=MD5, qop=auth, nc=00000001, cnonce=\""));
+        assert!(!request.get_request_headers().any(|(k, _)| k == "authorization"));
+    }
+
+    #[test]
+    fn test_connect_reuses_resolved_addresses() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut request = Request::default_get_request("http://nonexistent.invalid/").unwrap();
+        request.resolved = Some(vec![listener.local_addr().unwrap()]);
+        assert!(crate::http::connect(&request, None).is_ok());
+        let mut headers = HashMap::new();
+        headers.insert("Location".to_string(), "/next".to_string());
+        let status = ResponseStatus(StatusCode::Redirection(307), None);
+        let next = request.next_redirect(&Response::from_parts(status, headers, "")).unwrap().unwrap();
+        assert_eq!(next.get_resolved(), None);
+    }
+
+    #[test]
+    fn test_see_other_redirect_drops_body() {
+        let (port, server) = serve(vec![
+            b"HTTP/1.1 303 See Other\r\nLocation: /result\r\nContent-Length: 0\r\n\r\n",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+        ]);
+        let mut request = Request::new(format!("http://127.0.0.1:{}/form", port), None, Some("a=1".to_string())).unwrap();
+        request.add_header("Content-Type", "application/x-www-form-urlencoded");
+        request.follow_redirects(1);
+        assert_eq!(request.execute().unwrap().get_status_code(), Some(200));
+        let heads = server.join().unwrap();
+        assert!(heads[0].contains("content-length: 3\r\n"));
+        assert!(heads[1].starts_with("GET /result HTTP/1.1\r\n"));
+        assert!(!heads[1].to_ascii_lowercase().contains("content-"));
+    }
+
+    #[test]
+    fn test_temporary_and_permanent_redirects_keep_body() {
+        let redirects: [&'static [u8]; 2] = [
+            b"HTTP/1.1 307 Temporary Redirect\r\nLocation: /b\r\nContent-Length: 0\r\n\r\n",
+            b"HTTP/1.1 308 Permanent Redirect\r\nLocation: /b\r\nContent-Length: 0\r\n\r\n",
+        ];
+        for redirect in redirects.iter() {
+            let (port, server) = serve(vec![redirect, b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"]);
+            let mut request = Request::new(format!("http://127.0.0.1:{}/a", port), None, Some("a=1".to_string())).unwrap();
+            request.add_header("Content-Type", "application/x-www-form-urlencoded");
+            request.follow_redirects(1);
+            assert_eq!(request.execute().unwrap().get_status_code(), Some(200));
+            let heads = server.join().unwrap();
+            assert!(heads[1].starts_with("GET /b HTTP/1.1\r\n"));
+            assert!(heads[1].contains("Content-Type: application/x-www-form-urlencoded\r\n"));
+            assert!(heads[1].ends_with("\r\n\r\na=1"));
+        }
+    }
+
+    #[test]
+    fn test_temporary_redirect_with_body_from_reader_is_not_followed() {
+        let (port, server) = serve(vec![b"HTTP/1.1 307 Temporary Redirect\r\nLocation: /b\r\nContent-Length: 0\r\n\r\n"]);
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/a", port)).unwrap();
+        request.body_from_reader(&b"a=1"[..], Some(3));
+        request.follow_redirects(1);
+        assert_eq!(request.execute().unwrap().get_status_code(), Some(307));
+        assert!(server.join().unwrap()[0].ends_with("a=1"));
+    }
+
+    #[test]
+    fn test_on_redirect() {
+        let (port, server) = serve(vec![
+            b"HTTP/1.1 302 Found\r\nLocation: /b\r\nSet-Cookie: hop=1\r\nContent-Length: 0\r\n\r\n",
+            b"HTTP/1.1 302 Found\r\nLocation: /c\r\nSet-Cookie: hop=2\r\nContent-Length: 0\r\n\r\n",
+        ]);
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/a", port)).unwrap();
+        request.follow_redirects(5);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&seen);
+        request.on_redirect(Box::new(move |response, next| {
+            let cookie = response.header_values("set-cookie").concat();
+            recorder.lock().unwrap().push((cookie, next.path.clone()));
+            next.path != "/c"
+        }));
+        let response = request.execute().unwrap();
+        server.join().unwrap();
+        assert_eq!(response.location(), Some("/c"));
+        assert_eq!(*seen.lock().unwrap(), vec![
+            ("hop=1".to_string(), "/b".to_string()),
+            ("hop=2".to_string(), "/c".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_set_host_header() {
+        let (port, server) = serve(vec![b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"]);
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/", port)).unwrap();
+        request.set_host_header("vhost.example.test").unwrap();
+        assert_eq!(request.set_host_header("a\r\nX: y").unwrap_err().kind(), &ErrorKind::ParseError);
+        assert_eq!(request.execute().unwrap().get_status_code(), Some(200));
+        let head = &server.join().unwrap()[0];
+        assert!(head.contains("\r\nhost: vhost.example.test\r\n"));
+        assert!(!head.contains("127.0.0.1"));
+    }
+
+    #[test]
+    fn test_multipart_body() {
+        let mut builder = crate::MultipartBuilder::new();
+        builder.add_text("title", "report");
+        builder.add_file("file", "a.csv", "text/csv", b"a,b\r\n1,2");
+        let form = builder.build();
+        let (port, server) = serve(vec![b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"]);
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/upload", port)).unwrap();
+        request.multipart_body(form.clone());
+        request.execute().unwrap();
+        let sent = &server.join().unwrap()[0];
+        assert!(sent.contains(&format!("\r\nContent-Type: multipart/form-data; boundary={}\r\n", form.boundary())));
+        assert!(sent.contains(&format!("\r\ncontent-length: {}\r\n", form.body().len())));
+        let (_, body) = sent.split_once("\r\n\r\n").unwrap();
+        assert_eq!(body.as_bytes(), form.body());
+        assert!(body.ends_with(&format!("\r\n--{}--\r\n", form.boundary())));
+
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type".to_string(), form.content_type());
+        let parts = Response::from_parts(ResponseStatus(crate::StatusCode::Success(200), None), headers, body)
+            .multipart_parts().unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].header("content-disposition"), Some("form-data; name=\"title\""));
+        assert_eq!(parts[0].body, b"report");
+        assert_eq!(parts[1].header("content-disposition"), Some("form-data; name=\"file\"; filename=\"a.csv\""));
+        assert_eq!(parts[1].header("content-type"), Some("text/csv"));
+        assert_eq!(parts[1].body, b"a,b\r\n1,2");
+    }
+
+    #[test]
+    fn test_capture_exchange() {
+        const RAW: &[u8] = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nok\r\n0\r\n\r\n";
+        let (port, server) = serve(vec![RAW, RAW, RAW]);
+        let mut request = Request::new(format!("http://127.0.0.1:{}/", port), None, Some("a=1".to_string())).unwrap();
+        assert!(request.execute().unwrap().raw_exchange().is_none());
+        request.capture_exchange(true);
+        let response = request.execute().unwrap();
+        let exchange = response.raw_exchange().unwrap();
+        assert_eq!(exchange.response, RAW);
+        assert_eq!(response.body, "ok");
+        let response = request.execute_head_only().unwrap();
+        assert!(response.raw_exchange().unwrap().response.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        let sent = server.join().unwrap();
+        assert_eq!(String::from_utf8(exchange.request.clone()).unwrap(), sent[1]);
+        assert!(sent[1].ends_with("\r\n\r\na=1"));
+        assert_eq!(response.raw_exchange().unwrap().request, sent[2].as_bytes());
+    }
+
+    #[test]
+    fn test_expect_status() {
+        let (port, server) = serve(vec![
+            b"HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\n\r\n",
+            b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n",
+            b"HTTP/1.1 302 Found\r\nLocation: /b\r\nContent-Length: 0\r\n\r\n",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+            b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n",
+            b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n",
+        ]);
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/a", port)).unwrap();
+        request.expect_status(&[200, 201]);
+        let err = request.execute().unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::HttpStatus(202));
+        assert_eq!(err.source().unwrap().to_string(), "unexpected status 202, expected one of [200, 201]");
+        assert_eq!(request.execute().unwrap().get_status_code(), Some(201));
+        // only the final response of the redirects is checked.
+        request.follow_redirects(1);
+        assert_eq!(request.execute().unwrap().body, "ok");
+        let err = request.execute_into(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::HttpStatus(500));
+        request.expect_status(&[]);
+        assert_eq!(request.execute().unwrap().get_status_code(), Some(500));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_options_star() {
+        let (port, server) = serve(vec![b"HTTP/1.1 200 OK\r\nAllow: GET, HEAD,OPTIONS\r\nContent-Length: 0\r\n\r\n"]);
+        let mut request = Request::options_star(format!("http://127.0.0.1:{}/ignored", port)).unwrap();
+        assert_eq!(request.get_request_type(), "OPTIONS");
+        let response = request.execute().unwrap();
+        assert_eq!(response.allowed_methods(), vec!["GET", "HEAD", "OPTIONS"]);
+        assert!(server.join().unwrap()[0].starts_with("OPTIONS * HTTP/1.1\r\n"));
+        request.reset_body();
+        assert_eq!(request.request_line(), "GET /ignored HTTP/1.1");
+    }
+
+    #[test]
+    fn test_response_peer_addr() {
+        let (port, server) = serve(vec![b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"]);
+        let mut request = Request::default_get_request(format!("http://nonexistent.invalid:{}/", port)).unwrap();
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        request.connect_timeout(Duration::from_secs(5));
+        request.resolved = Some(vec![closed, ([127, 0, 0, 1], port).into()]);
+        let response = request.execute().unwrap();
+        server.join().unwrap();
+        assert_eq!(response.peer_addr(), Some(([127, 0, 0, 1], port).into()));
+        assert_eq!(response.peer_certificate(), None);
+        assert_eq!(response.url().get_full_url(), request.url.get_full_url());
+    }
+
+    #[test]
+    fn test_follow_redirects() {
+        let (port, server) = serve(vec![
+            b"HTTP/1.1 302 Found\r\nLocation: /b\r\nContent-Length: 0\r\n\r\n",
+            b"HTTP/1.1 301 Moved Permanently\r\nLocation: http://127.0.0.1/c\r\nContent-Length: 0\r\n\r\n",
+        ]);
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/a", port)).unwrap();
+        request.add_header("Authorization", "Bearer secret");
+        request.body = Some("data".to_string());
+        request.follow_redirects(5);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&seen);
+        request.redirect_policy(Box::new(move |current, next| {
+            recorder.lock().unwrap().push((current.path.clone(), next.get_full_url()));
+            next.port != "80"
+        }));
+        let response = request.execute().unwrap();
+        assert_eq!(response.get_status_code(), Some(301));
+        assert_eq!((response.url().host.as_str(), response.url().path.as_str()), ("127.0.0.1", "/b"));
+        assert_eq!(*seen.lock().unwrap(), vec![
+            ("/a".to_string(), format!("http://127.0.0.1:{}/b", port)),
+            ("/b".to_string(), "http://127.0.0.1:80/c".to_string()),
+        ]);
+        let heads = server.join().unwrap();
+        assert!(heads[1].starts_with("GET /b HTTP/1.1\r\n"));
+        assert!(heads[1].contains("\r\nAuthorization: Bearer secret\r\n"));
+        assert!(!heads[1].contains("content-length"));
+    }
+
+    #[test]
+    fn test_redirect_limit() {
+        let (port, server) = serve(vec![
+            b"HTTP/1.1 302 Found\r\nLocation: /b\r\nContent-Length: 0\r\n\r\n",
+            b"HTTP/1.1 302 Found\r\nLocation: /c\r\nContent-Length: 0\r\n\r\n",
+        ]);
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/a", port)).unwrap();
+        request.follow_redirects(1);
+        let response = request.execute().unwrap();
+        assert_eq!(response.location(), Some("/c"));
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_overall_timeout_covers_all_redirects() {
+        let mut hops = vec![&b"/b"[..], b"/c"].into_iter();
+        let (port, server) = serve_with(3, move |mut stream, _, _| {
+            std::thread::sleep(Duration::from_millis(250));
+            let response = match hops.next() {
+                Some(next) => [&b"HTTP/1.1 302 Found\r\nContent-Length: 0\r\nLocation: "[..], next, b"\r\n\r\n"].concat(),
+                None => b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".to_vec(),
+            };
+            // the client may have given up already.
+            let _ = stream.write_all(&response);
+        });
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/a", port)).unwrap();
+        request.follow_redirects(5);
+        request.timeout(Duration::from_millis(600));
+        let err = request.execute().unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Timeout);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_overall_timeout_bounds_each_read() {
+        let (port, server) = serve_with(1, |mut stream, mut reader, _| {
+            std::thread::sleep(Duration::from_millis(200));
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nab").unwrap();
+            // stalls until the client gives up.
+            let _ = reader.read_to_end(&mut Vec::new());
+        });
+        let mut request = Request::default_get_request(format!("http://127.0.0.1:{}/", port)).unwrap();
+        request.timeout(Duration::from_millis(400));
+        let started = Instant::now();
+        let err = request.execute().unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Timeout);
+        assert!(started.elapsed() < Duration::from_millis(550), "{:?}", started.elapsed());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_redirect_to_other_origin_drops_credentials() {
+        let mut request = Request::default_get_request("http://example.com/a").unwrap();
+        request.add_header("authorization", "Bearer secret");
+        request.add_header("x-trace", "1");
+        let mut headers = HashMap::new();
+        headers.insert("Location".to_string(), "https://example.com/b".to_string());
+        let status = ResponseStatus(StatusCode::Redirection(302), None);
+        let next = request.next_redirect(&Response::from_parts(status, headers, "")).unwrap().unwrap();
+        assert_eq!(next.url.get_full_url(), "https://example.com:443/b");
+        assert!(!next.get_request_headers().any(|(k, _)| k == "authorization"));
+        assert!(next.get_request_headers().any(|(k, _)| k == "x-trace"));
+    }
+
+    #[test]
+    fn test_require_https_rejects_downgrade() {
+        let mut request = Request::default_get_request("https://example.com/a").unwrap();
+        request.require_https(true);
+        let redirect = |location: &str| {
+            let mut headers = HashMap::new();
+            headers.insert("Location".to_string(), location.to_string());
+            Response::from_parts(ResponseStatus(StatusCode::Redirection(301), None), headers, "")
+        };
+        let err = request.next_redirect(&redirect("http://example.com/a")).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InsecureRedirect);
+        let next = request.next_redirect(&redirect("https://other.example.com/b")).unwrap().unwrap();
+        assert_eq!(next.url.host, "other.example.com");
+        request.require_https(false);
+        assert!(request.next_redirect(&redirect("http://example.com/a")).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_uppercase_scheme_is_https() {
+        let mut request = Request::default_get_request("HTTPS://example.com/a").unwrap();
+        assert!(request.is_https());
+        assert_eq!(request.port(), 443);
+        request.require_https(true);
+        request.add_header("authorization", "Bearer secret");
+        let mut headers = HashMap::new();
+        headers.insert("Location".to_string(), "https://Example.com/b".to_string());
+        let status = ResponseStatus(StatusCode::Redirection(302), None);
+        let next = request.next_redirect(&Response::from_parts(status, headers, "")).unwrap().unwrap();
+        assert!(next.is_https());
+        assert!(next.get_request_headers().any(|(k, _)| k == "authorization"));
+    }
+
+    #[test]
+    fn test_with_defaults() {
+        let mut defaults = DefaultHeaders::new();
+        defaults.add("accept", "text/plain").unwrap();
+        defaults.add("Accept", "application/json").unwrap();
+        assert_eq!(defaults.add("bad header", "x").unwrap_err().kind(), &ErrorKind::ParseError);
+        let request = Request::with_defaults("http://example.com", &defaults).unwrap();
+        let accept: Vec<(&str, &str)> = request.get_request_headers()
+            .filter(|(k, _)| k.eq_ignore_ascii_case("accept"))
+            .collect();
+        assert_eq!(accept, vec![("Accept", "application/json")]);
+    }
+
+    #[test]
+    fn test_new_accepts_valid_headers() {
+        let headers = vec![("x-custom_header", "some value\twith tab"), ("Accept", "*/*"), ("x-empty", "")];
+        assert!(Request::new("http://example.com", Some(headers), None).is_ok());
+    }
+
+    #[test]
+    fn test_new_rejects_invalid_headers() {
+        let invalid: Vec<Header> = vec![
+            ("", "value"),
+            ("bad name", "value"),
+            ("bad:name", "value"),
+            ("bad\u{7}name", "value"),
+            ("x-header", "value\r\nx-injected: yes"),
+            ("x-header", "value\n"),
+            ("x-header", "caf\u{e9}"),
+        ];
+        for header in invalid {
+            let err = Request::new("http://example.com", Some(vec![header]), None).unwrap_err();
+            let err = err.downcast_ref::<NanoGetError>().unwrap();
+            assert_eq!(err.kind(), &ErrorKind::ParseError, "{:?}", header);
+        }
+    }
+}
@@ -1,7 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Error, Formatter};
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime};
 
-use super::url::Tuple;
+use super::date::parse_http_date;
+use super::errors::{ErrorKind, NanoGetError};
+use super::Request;
+use super::request::{is_hop_by_hop_header, BodyMode};
+use super::url::{Tuple, Url};
 
 /// This is the HTTP Reponse Object.
 ///
@@ -10,7 +16,7 @@ use super::url::Tuple;
 /// This allows inspection of the HTTP Status Code & Reason and HTTP Response Body.
 ///
 /// ## Example
-/// ```rust
+/// ```rust,no_run
 /// use nano_get::Response;
 /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
 /// request.add_header("test", "value testing");
@@ -18,20 +24,79 @@ use super::url::Tuple;
 /// println!("Status: {}", response.status);
 /// println!("Body: {}", response.body);
 /// ```
+#[derive(Debug)]
 pub struct Response {
     /// The status of the Response.
     pub status: ResponseStatus,
-    /// The body of the Response.
+    /// The body of the Response as text.
+    ///
+    /// Any invalid UTF-8 sequences are replaced. Use `body_bytes()` for the exact bytes received.
+    /// This is empty for binary bodies, as decided by `Request::body_mode`.
     pub body: String,
+    body_bytes: Vec<u8>,
     headers: Option<HashMap<String, String>>,
+    trailers: Option<HashMap<String, String>>,
+    bytes_received: usize,
+    url: Url,
+    status_line: String,
+    peer_addr: Option<SocketAddr>,
+    peer_certificate: Option<Vec<u8>>,
+    raw_exchange: Option<RawExchange>,
+    links: Vec<(String, String)>,
+    informational: Vec<InformationalResponse>,
 }
 
 impl Response {
+    /// Creates a Response from its parts, for example to stub out responses in tests.
+    ///
+    /// The status line is built from the status as `HTTP/1.1 <code> <reason>`. As the Response was
+    /// not received from a server, its url is `http://localhost/` and `bytes_received()` is 0.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use nano_get::{Response, ResponseStatus, StatusCode};
+    ///
+    /// let mut headers = HashMap::new();
+    /// headers.insert("Content-Type".to_string(), "application/json".to_string());
+    /// let status = ResponseStatus(StatusCode::Success(200), Some("OK".to_string()));
+    /// let response = Response::from_parts(status, headers, "{}");
+    /// assert_eq!(response.get_status_code(), Some(200));
+    /// assert_eq!(response.body, "{}");
+    /// ```
+    pub fn from_parts(status: ResponseStatus, headers: HashMap<String, String>, body: impl Into<Vec<u8>>) -> Response {
+        let body_bytes = body.into();
+        let code = status.0.get_code().map_or_else(String::new, |code| code.to_string());
+        let status_line = match status.1.as_ref() {
+            Some(reason) => format!("HTTP/1.1 {} {}", code, reason),
+            None => format!("HTTP/1.1 {}", code),
+        };
+        let links = headers.iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case("link"))
+            .flat_map(|(_, v)| parse_links(v, None))
+            .collect();
+        Response {
+            status,
+            body: String::from_utf8_lossy(&body_bytes).into_owned(),
+            body_bytes,
+            headers: Some(headers),
+            trailers: None,
+            bytes_received: 0,
+            url: Url::new("http://localhost/"),
+            status_line,
+            peer_addr: None,
+            peer_certificate: None,
+            raw_exchange: None,
+            links,
+            informational: Vec::new(),
+        }
+    }
+
     /// Get an iterator of the Headers in the Response.
     ///
     /// ## Example
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// use nano_get::Response;
     ///
     /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
@@ -48,31 +113,965 @@ impl Response {
         }))
     }
 
+    /// Get an iterator of the trailer Headers sent after the last chunk of a chunked body.
+    ///
+    /// This is `None` if the body was not chunked. The trailers are kept apart from the headers, as
+    /// they are only known once the whole body has been received.
+    pub fn trailers(&self) -> Option<impl Iterator<Item=(&str, &str)>> {
+        Some(self.trailers.as_ref()?.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+    }
+
+    /// Get an iterator of the end-to-end Headers in the Response.
+    ///
+    /// This leaves out the hop-by-hop headers (see `Request::remove_hop_by_hop_headers`), which
+    /// must not be passed along when forwarding the response.
+    pub fn end_to_end_headers(&self) -> impl Iterator<Item=(&str, &str)> {
+        let connection = self.find_header("connection");
+        self.headers.iter()
+            .flat_map(|headers| headers.iter())
+            .filter(move |(k, _)| !is_hop_by_hop_header(k, connection))
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Get an iterator of the names of the Headers in the Response.
+    ///
+    /// The names are returned as sent by the server, without duplicates (ignoring case).
+    pub fn header_names(&self) -> impl Iterator<Item=&str> {
+        let mut seen = HashSet::new();
+        self.headers.iter()
+            .flat_map(|headers| headers.keys())
+            .map(|k| k.as_str())
+            .filter(move |k| seen.insert(k.to_ascii_lowercase()))
+    }
+
     /// Returns the status code of the Response as an unsigned 16-bit Integer (u16).
     ///
     /// Provided as a convenience. This can be got through the embedded `ResponseStatus` also.
     pub fn get_status_code(&self) -> Option<u16> {
         self.status.0.get_code()
     }
+
+    /// Returns the size of the Response body in bytes, as received after removing the transfer
+    /// framing, i.e. the length of `body_bytes()`.
+    ///
+    /// This is the length to declare when forwarding the body. It can differ from the
+    /// `content_length()` the server declared: a chunked body has no `Content-Length`, and a
+    /// body cut short is smaller than declared. As content codings are never decoded, there is no
+    /// separate decompressed size.
+    pub fn body_len(&self) -> usize {
+        self.body_bytes.len()
+    }
+
+    /// Returns the length of the body declared by the `Content-Length` header, if present and valid.
+    ///
+    /// See `body_len` for the size of the body as actually received.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use nano_get::{Response, ResponseStatus, StatusCode};
+    ///
+    /// let mut headers = HashMap::new();
+    /// headers.insert("Content-Length".to_string(), "10".to_string());
+    /// let response = Response::from_parts(ResponseStatus(StatusCode::Success(200), None), headers, "short");
+    /// assert_eq!(response.content_length(), Some(10));
+    /// assert_eq!(response.body_len(), 5);
+    /// ```
+    pub fn content_length(&self) -> Option<u64> {
+        self.find_header("content-length")?.trim().parse().ok()
+    }
+
+    /// Returns the body of the Response exactly as it was received.
+    ///
+    /// Unlike the `body` field, this is safe to use for binary content.
+    ///
+    /// Only the transfer framing is removed (a chunked body is decoded). Content codings are never
+    /// decoded, so a body sent with `Content-Encoding: gzip` is returned compressed, in the form to
+    /// forward as-is. See also `looks_gzipped`.
+    pub fn body_bytes(&self) -> &[u8] {
+        &self.body_bytes
+    }
+
+    /// Checks if the body looks gzip-compressed even though the Response has no `Content-Encoding`,
+    /// i.e. it starts with the gzip magic bytes (`1f 8b`).
+    ///
+    /// Some misconfigured servers compress the body but leave out the header. This crate does not
+    /// decompress bodies, so this lets the caller decide to decompress `body_bytes()` anyway.
+    ///
+    /// This is only a guess: a binary body (like an image or an archive of another format) can
+    /// start with the same two bytes by chance. Only act on it for responses that are known to
+    /// carry text.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use nano_get::{Response, ResponseStatus, StatusCode};
+    ///
+    /// let body = vec![0x1f, 0x8b, 0x08, 0x00];
+    /// let response = Response::from_parts(ResponseStatus(StatusCode::Success(200), None), HashMap::new(), body);
+    /// assert!(response.looks_gzipped());
+    /// ```
+    pub fn looks_gzipped(&self) -> bool {
+        self.find_header("content-encoding").is_none() && self.body_bytes.starts_with(&[0x1f, 0x8b])
+    }
+
+    /// Returns the body of the Response as a `&str`, without copying it.
+    ///
+    /// Returns an error of kind `ParseError` if the body is not valid UTF-8.
+    pub fn text(&self) -> Result<&str, NanoGetError> {
+        std::str::from_utf8(&self.body_bytes).map_err(|_err| NanoGetError::new(ErrorKind::ParseError))
+    }
+
+    /// Returns the HTTP version of the Response, as sent in the status line (like `HTTP/1.1`).
+    pub fn http_version(&self) -> &str {
+        self.status_line.split(' ').next().unwrap_or("")
+    }
+
+    /// Returns the status line of the Response exactly as it was sent (like `HTTP/1.1 200 OK`).
+    pub fn status_line(&self) -> &str {
+        &self.status_line
+    }
+
+    /// Returns the comma-separated elements of the value of a list header, like `Cache-Control`,
+    /// `Accept` or `Vary`, with the whitespace around them trimmed.
+    ///
+    /// A comma inside a quoted string does not split the value, and the quotes are kept. Empty
+    /// elements are left out. Returns an empty list if the header is absent. The name is matched
+    /// case-insensitively.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use nano_get::{Response, ResponseStatus, StatusCode};
+    ///
+    /// let mut headers = HashMap::new();
+    /// headers.insert("Cache-Control".to_string(), "no-cache, max-age=0".to_string());
+    /// let response = Response::from_parts(ResponseStatus(StatusCode::Success(200), None), headers, "");
+    /// assert_eq!(response.header_values("cache-control"), vec!["no-cache", "max-age=0"]);
+    /// ```
+    pub fn header_values(&self, name: &str) -> Vec<&str> {
+        self.find_header(name).map_or_else(Vec::new, split_list)
+    }
+
+    /// Splits a `multipart/*` body (like `multipart/mixed` or `multipart/form-data`) into its parts,
+    /// each with its own headers and body bytes.
+    ///
+    /// The boundary is taken from the `Content-Type` of the Response. The preamble before the first
+    /// boundary and the epilogue after the last one are ignored.
+    ///
+    /// Returns an error of kind `ParseError` if the Response is not multipart, the boundary is
+    /// missing or invalid, or the body is malformed (like a body cut short before its closing
+    /// boundary).
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use nano_get::{Response, ResponseStatus, StatusCode};
+    ///
+    /// let mut headers = HashMap::new();
+    /// headers.insert("Content-Type".to_string(), "multipart/mixed; boundary=sep".to_string());
+    /// let body = "--sep\r\nContent-Type: text/plain\r\n\r\nhello\r\n--sep\r\n\r\nworld\r\n--sep--\r\n";
+    /// let response = Response::from_parts(ResponseStatus(StatusCode::Success(200), None), headers, body);
+    /// let parts = response.multipart_parts().unwrap();
+    /// assert_eq!(parts[0].header("content-type"), Some("text/plain"));
+    /// assert_eq!(parts[0].body, b"hello");
+    /// assert_eq!(parts[1].body, b"world");
+    /// ```
+    pub fn multipart_parts(&self) -> Result<Vec<MultipartPart>, NanoGetError> {
+        let parse_error = || NanoGetError::new(ErrorKind::ParseError);
+        let boundary = self.find_header("content-type").and_then(multipart_boundary).ok_or_else(parse_error)?;
+        let delimiter = format!("\r\n--{}", boundary).into_bytes();
+        // the first boundary may start the body, without a line break before it.
+        let body = [b"\r\n", self.body_bytes.as_slice()].concat();
+        let start = find_bytes(&body, &delimiter).ok_or_else(parse_error)?;
+        let mut rest = &body[start + delimiter.len()..];
+        let mut parts = Vec::new();
+        loop {
+            if rest.starts_with(b"--") {
+                return Ok(parts);
+            }
+            let line_end = find_bytes(rest, b"\r\n").ok_or_else(parse_error)?;
+            if !rest[..line_end].iter().all(|b| *b == b' ' || *b == b'\t') {
+                return Err(parse_error());
+            }
+            rest = &rest[line_end + 2..];
+            let end = find_bytes(rest, &delimiter).ok_or_else(parse_error)?;
+            parts.push(MultipartPart::parse(&rest[..end]).ok_or_else(parse_error)?);
+            rest = &rest[end + delimiter.len()..];
+        }
+    }
+
+    /// Returns the methods listed in the `Allow` header, like in the response to an `OPTIONS`
+    /// request (see `Request::options_star`), or to a request with a method the resource does not
+    /// support (`405 Method Not Allowed`).
+    ///
+    /// Returns an empty list if the header is absent.
+    pub fn allowed_methods(&self) -> Vec<&str> {
+        self.header_values("allow")
+    }
+
+    /// Returns the value of the `Transfer-Encoding` header, if present.
+    ///
+    /// This is the transfer encoding the Response was sent with, even though a chunked body has
+    /// already been decoded into `body_bytes()`.
+    pub fn transfer_encoding(&self) -> Option<&str> {
+        self.find_header("transfer-encoding")
+    }
+
+    /// Checks if the Response was sent with a chunked body, i.e. if `chunked` is the last of its
+    /// transfer encodings.
+    ///
+    /// The body itself is always decoded. This is useful when forwarding the Response, to decide
+    /// whether to re-chunk it.
+    pub fn is_chunked(&self) -> bool {
+        self.transfer_encoding()
+            .and_then(|v| v.rsplit(',').next())
+            .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+    }
+
+    /// Returns the value of the `WWW-Authenticate` header, if present.
+    ///
+    /// This is sent with 401 responses, to tell the client how to authenticate. See
+    /// `auth_challenge` for the parsed form.
+    pub fn www_authenticate(&self) -> Option<&str> {
+        self.find_header("www-authenticate")
+    }
+
+    /// Returns the first challenge of the `WWW-Authenticate` header, split into its scheme and
+    /// parameters.
+    ///
+    /// Returns `None` if the header is absent or invalid.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use nano_get::{Response, ResponseStatus, StatusCode};
+    ///
+    /// let mut headers = HashMap::new();
+    /// headers.insert("WWW-Authenticate".to_string(), r#"Bearer realm="api", error="invalid_token""#.to_string());
+    /// let status = ResponseStatus(StatusCode::ClientError(401), Some("Unauthorized".to_string()));
+    /// let response = Response::from_parts(status, headers, "");
+    /// let challenge = response.auth_challenge().unwrap();
+    /// assert_eq!(challenge.scheme, "Bearer");
+    /// assert_eq!(challenge.param("error"), Some("invalid_token"));
+    /// ```
+    pub fn auth_challenge(&self) -> Option<AuthChallenge> {
+        AuthChallenge::parse(self.www_authenticate()?)
+    }
+
+    /// Returns the value of the `Location` header, if present.
+    pub fn location(&self) -> Option<&str> {
+        self.find_header("location")
+    }
+
+    /// Returns the value of the `Location` header resolved against the given base url (usually the
+    /// url of the request), using `Url::join`.
+    ///
+    /// Returns `None` if the header is absent, and an error of kind `ParseError` if it is invalid.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use nano_get::{Response, ResponseStatus, StatusCode, Url};
+    ///
+    /// let mut headers = HashMap::new();
+    /// headers.insert("Location".to_string(), "../login".to_string());
+    /// let status = ResponseStatus(StatusCode::Redirection(302), Some("Found".to_string()));
+    /// let response = Response::from_parts(status, headers, "");
+    /// let base = Url::new("http://example.com/app/home");
+    /// assert_eq!(response.location_url(&base).unwrap().unwrap().path, "/login");
+    /// ```
+    pub fn location_url(&self, base: &Url) -> Option<Result<Url, NanoGetError>> {
+        Some(base.join(self.location()?))
+    }
+
+    /// Returns the links of the `Link` headers, as `(rel, url)` pairs in the order they were sent,
+    /// like the pages of a paginated API.
+    ///
+    /// All the links of all the `Link` headers are included. A link with several relation types
+    /// (like `rel="next last"`) is listed once for each of them, and links without a `rel` are left
+    /// out. The relation types are lowercased, and relative urls are resolved against `url()` for a
+    /// Response received from a server.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use nano_get::{Response, ResponseStatus, StatusCode};
+    ///
+    /// let mut headers = HashMap::new();
+    /// let link = "<https://example.com/items?page=2>; rel=\"next\", <https://example.com/items?page=9>; rel=last";
+    /// headers.insert("Link".to_string(), link.to_string());
+    /// let response = Response::from_parts(ResponseStatus(StatusCode::Success(200), None), headers, "[]");
+    /// assert_eq!(response.links()[1], ("last".to_string(), "https://example.com/items?page=9".to_string()));
+    /// assert_eq!(response.next_link(), Some("https://example.com/items?page=2"));
+    /// ```
+    pub fn links(&self) -> &[(String, String)] {
+        &self.links
+    }
+
+    /// Returns the url of the first link with the relation type `next`, if any (see `links`).
+    pub fn next_link(&self) -> Option<&str> {
+        self.links.iter()
+            .find(|(rel, _)| rel == "next")
+            .map(|(_, url)| url.as_str())
+    }
+
+    /// Returns the time at which the Response was generated, as given by the `Date` header.
+    ///
+    /// Returns `None` if the header is absent or not a valid HTTP-date.
+    pub fn date(&self) -> Option<SystemTime> {
+        parse_http_date(self.find_header("date")?)
+    }
+
+    /// Returns the time the Response has been held in caches, as given by the `Age` header.
+    ///
+    /// Returns `None` if the header is absent or invalid.
+    pub fn age(&self) -> Option<Duration> {
+        parse_delta_seconds(self.find_header("age")?)
+    }
+
+    /// Returns how long to wait before retrying, as given by the `Retry-After` header (commonly sent
+    /// with 429 & 503 responses).
+    ///
+    /// Both the delay in seconds and the HTTP-date forms are supported. A date in the past gives a
+    /// zero duration. Returns `None` if the header is absent or invalid.
+    pub fn retry_after(&self) -> Option<Duration> {
+        let value = self.find_header("retry-after")?;
+        if let Some(delay) = parse_delta_seconds(value) {
+            return Some(delay);
+        }
+        let date = parse_http_date(value)?;
+        Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+    }
+
+    /// Checks if a shared cache may store the Response and reuse it for later requests, as per a
+    /// subset of the rules of [RFC 7234](https://tools.ietf.org/html/rfc7234#section-3).
+    ///
+    /// The rules implemented are:
+    /// 1. A `Cache-Control` with `no-store`, `private` or `no-cache` is never cacheable. (A
+    ///    `no-cache` Response may be stored, but must be revalidated before every reuse.)
+    /// 2. Likewise, a `max-age` (or `s-maxage`) of 0 makes the Response stale right away.
+    /// 3. A Response with a positive `max-age` or `s-maxage`, or an `Expires` date in the future,
+    ///    is cacheable, whatever its status. `s-maxage` takes precedence over `max-age`, which
+    ///    takes precedence over `Expires`.
+    /// 4. Otherwise, only the statuses that are cacheable by default are: 200, 203, 204, 206, 300,
+    ///    301, 404, 405, 410, 414 and 501.
+    ///
+    /// The method is not checked, as the requests of this crate are all GET requests, which are
+    /// cacheable. Interim and unparseable statuses are never cacheable. Validators (`ETag` and
+    /// `Last-Modified`) and the `Vary` header are left to the cache.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use nano_get::{Response, ResponseStatus, StatusCode};
+    ///
+    /// let mut headers = HashMap::new();
+    /// headers.insert("Cache-Control".to_string(), "public, max-age=3600".to_string());
+    /// let response = Response::from_parts(ResponseStatus(StatusCode::ClientError(403), None), headers, "");
+    /// assert!(response.is_cacheable());
+    /// ```
+    pub fn is_cacheable(&self) -> bool {
+        let code = match self.status.0.get_code() {
+            Some(code) if code >= 200 => code,
+            _ => return false,
+        };
+        let mut max_age = None;
+        let mut shared_max_age = None;
+        for directive in self.header_values("cache-control") {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (directive, None),
+            };
+            if ["no-store", "private", "no-cache"].iter().any(|n| name.eq_ignore_ascii_case(n)) {
+                return false;
+            }
+            if name.eq_ignore_ascii_case("max-age") {
+                max_age = value.and_then(parse_delta_seconds);
+            } else if name.eq_ignore_ascii_case("s-maxage") {
+                shared_max_age = value.and_then(parse_delta_seconds);
+            }
+        }
+        if let Some(age) = shared_max_age.or(max_age) {
+            return age > Duration::from_secs(0);
+        }
+        let expires = self.find_header("expires").and_then(parse_http_date);
+        if expires.is_some_and(|expires| expires > SystemTime::now()) {
+            return true;
+        }
+        matches!(code, 200 | 203 | 204 | 206 | 300 | 301 | 404 | 405 | 410 | 414 | 501)
+    }
+
+    /// Follows a single redirect.
+    ///
+    /// If this is a redirect response (301, 302, 303, 307 or 308) with a `Location` header, a GET
+    /// request is issued to the location (resolved against the url of this response) and the next
+    /// response is returned. Otherwise, returns `None`.
+    ///
+    /// This lets you implement your own redirect policy, one step at a time.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// let mut response = request.execute().unwrap();
+    /// for _ in 0..5 {
+    ///     match response.follow() {
+    ///         Some(next) => response = next.unwrap(),
+    ///         None => break,
+    ///     }
+    /// }
+    /// ```
+    pub fn follow(&self) -> Option<Result<Response, NanoGetError>> {
+        if !self.is_followable_redirect() {
+            return None;
+        }
+        let next = self.location_url(&self.url)?;
+        Some(next.and_then(|url| Request::from_url(url, None, None)?.execute()))
+    }
+
+    /// Checks if this is a redirect that can be followed (301, 302, 303, 307 or 308), as long as it
+    /// has a `Location` header.
+    pub(crate) fn is_followable_redirect(&self) -> bool {
+        matches!(self.status.0.get_code(), Some(301) | Some(302) | Some(303) | Some(307) | Some(308))
+    }
+
+    fn find_header(&self, key: &str) -> Option<&str> {
+        self.headers.as_ref()?.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the url the Response was received from, with its parsed `protocol`, `host`, `port`
+    /// and `path`.
+    ///
+    /// When redirects are followed (see `Request::follow_redirects`), this is the url the last
+    /// request was sent to, i.e. where the request ended up. Otherwise, it equals the url of the
+    /// request. A Response built with `from_parts` has the url `http://localhost/`.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/old").unwrap();
+    /// request.follow_redirects(5);
+    /// let response = request.execute().unwrap();
+    /// println!("served by {} at {}", response.url().host, response.url().path);
+    /// ```
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Returns the total number of bytes read for the Response, including the status line and headers.
+    ///
+    /// Useful for bandwidth accounting.
+    pub fn bytes_received(&self) -> usize {
+        self.bytes_received
+    }
+
+    /// Returns the address of the server the Response was received from.
+    ///
+    /// When the host resolves to several addresses, this is the one the connection was made to.
+    /// This is `None` for a Response that was not received over a connection (see `from_parts`).
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    pub(crate) fn set_peer_addr(&mut self, peer_addr: Option<SocketAddr>) {
+        self.peer_addr = peer_addr;
+    }
+
+    /// Returns the certificate the server presented during the TLS handshake, DER-encoded.
+    ///
+    /// This is the certificate of the server itself (not the rest of its chain), for inspecting it
+    /// after the fact: hash it, or parse it with a library of your choice, and compare it to the
+    /// expected value. This is not pinning, as the request (with its headers and body) has already
+    /// been sent to the server by the time the response is returned. This is `None` for plain http
+    /// responses.
+    pub fn peer_certificate(&self) -> Option<&[u8]> {
+        self.peer_certificate.as_deref()
+    }
+
+    #[cfg(feature = "https")]
+    pub(crate) fn set_peer_certificate(&mut self, peer_certificate: Option<Vec<u8>>) {
+        self.peer_certificate = peer_certificate;
+    }
+
+    /// Returns the interim (1xx) responses the server sent before this Response, in the order they
+    /// were received, like `103 Early Hints` with the resources to preload.
+    ///
+    /// This is always empty unless the request collects them (see `Request::collect_informational`).
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// let mut request = nano_get::Request::default_get_request("http://example.com/").unwrap();
+    /// request.collect_informational(true);
+    /// let response = request.execute().unwrap();
+    /// for hints in response.informational() {
+    ///     for link in hints.headers.iter().filter(|(k, _)| k.eq_ignore_ascii_case("link")) {
+    ///         println!("preload {}", link.1);
+    ///     }
+    /// }
+    /// ```
+    pub fn informational(&self) -> &[InformationalResponse] {
+        &self.informational
+    }
+
+    pub(crate) fn set_informational(&mut self, informational: Vec<InformationalResponse>) {
+        self.informational = informational;
+    }
+
+    /// Returns the exact bytes of the request as sent and of the response as received, if the
+    /// request captured them (see `Request::capture_exchange`).
+    pub fn raw_exchange(&self) -> Option<&RawExchange> {
+        self.raw_exchange.as_ref()
+    }
+
+    pub(crate) fn set_raw_exchange(&mut self, raw_exchange: RawExchange) {
+        self.raw_exchange = Some(raw_exchange);
+    }
+
+    /// Sets the trailers from the raw trailer section of a chunked body, along with the number of
+    /// bytes read for the Response, which differs from the decoded size.
+    pub(crate) fn set_chunked_parts(&mut self, trailers: &[u8], bytes_received: usize) {
+        let trailers = String::from_utf8_lossy(trailers);
+        let lines: Vec<&str> = trailers.split("\r\n").filter(|line| !line.is_empty()).collect();
+        self.trailers = Some(process_response_headers(&lines).unwrap_or_default());
+        self.bytes_received = bytes_received;
+    }
+}
+
+/// An interim (1xx) response, like `100 Continue` or `103 Early Hints`, received before the final
+/// Response.
+///
+/// See `Response::informational`.
+#[derive(Debug, Clone)]
+pub struct InformationalResponse {
+    /// The status of the interim response.
+    pub status: ResponseStatus,
+    /// The headers of the interim response, in the order they were sent. Repeated headers (like the
+    /// `Link` headers of `103 Early Hints`) are all kept.
+    pub headers: Vec<(String, String)>,
+}
+
+impl InformationalResponse {
+    /// Creates the interim response from its head, as read off the stream.
+    pub(crate) fn from_head(head: &[u8]) -> InformationalResponse {
+        let head = String::from_utf8_lossy(head);
+        let headers = head.split("\r\n")
+            .skip(1)
+            .filter_map(|line| line.split_once(':'))
+            .map(|(k, v)| (k.to_string(), v.trim().to_string()))
+            .collect();
+        InformationalResponse { status: parse_status(head.as_bytes()), headers }
+    }
+}
+
+/// The bytes of a request and its response, exactly as they were sent and received.
+///
+/// See `Response::raw_exchange`.
+#[derive(Debug, Clone, Default)]
+pub struct RawExchange {
+    /// The request line, headers and body of the request, as written to the connection.
+    pub request: Vec<u8>,
+    /// Everything read off the connection for the response: any interim responses, the head and
+    /// the body (with its chunked framing, if any).
+    pub response: Vec<u8>,
+}
+
+/// A part of a multipart body.
+///
+/// See `Response::multipart_parts`.
+#[derive(Debug, Clone)]
+pub struct MultipartPart {
+    /// The headers of the part, in the order they were sent.
+    pub headers: Vec<(String, String)>,
+    /// The body of the part, exactly as it was received.
+    pub body: Vec<u8>,
+}
+
+impl MultipartPart {
+    /// Returns the value of the first header with the given name (ignoring case).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Parses the part from the bytes between two boundaries, returning `None` if its headers
+    /// are not followed by an empty line.
+    fn parse(content: &[u8]) -> Option<MultipartPart> {
+        let (head, body) = match content.strip_prefix(b"\r\n") {
+            Some(body) => (&[][..], body),
+            None => {
+                let end = find_bytes(content, b"\r\n\r\n")?;
+                (&content[..end], &content[end + 4..])
+            }
+        };
+        let head = String::from_utf8_lossy(head);
+        let headers = head.split("\r\n")
+            .filter_map(|line| line.split_once(':'))
+            .map(|(k, v)| (k.to_string(), v.trim().to_string()))
+            .collect();
+        Some(MultipartPart { headers, body: body.to_vec() })
+    }
+}
+
+/// Returns the boundary of a multipart `Content-Type`, if it is one and the boundary is valid.
+fn multipart_boundary(content_type: &str) -> Option<String> {
+    let (media_type, mut rest) = content_type.split_once(';')?;
+    if !media_type.trim().to_ascii_lowercase().starts_with("multipart/") {
+        return None;
+    }
+    loop {
+        rest = rest.trim_start_matches([' ', '\t', ';']);
+        let (name, value) = rest.split_once('=')?;
+        let (value, remaining) = parse_param_value(value.trim_start())?;
+        if name.trim().eq_ignore_ascii_case("boundary") {
+            let valid = (1..=70).contains(&value.len()) && !value.ends_with(' ')
+                && value.chars().all(|c| c.is_ascii_alphanumeric() || "'()+_,-./:=? ".contains(c));
+            return if valid { Some(value) } else { None };
+        }
+        rest = remaining;
+    }
 }
 
-pub fn new_response_from_complete(response: String) -> Response {
-    let lines: Vec<&str> = response.splitn(2, "\r\n\r\n").collect();
-    let heads = (*lines.first().unwrap()).to_string();
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// A challenge from the `WWW-Authenticate` header, like `Basic realm="x"`.
+///
+/// See `Response::auth_challenge`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthChallenge {
+    /// The authentication scheme, like `Basic`, `Bearer` or `Digest`.
+    pub scheme: String,
+    /// The parameters of the challenge, in the order they were sent, with quoted values unquoted.
+    pub params: Vec<(String, String)>,
+}
+
+impl AuthChallenge {
+    /// Returns the value of the parameter with the given name (ignoring case).
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Parses the first challenge of the header value. Any further challenges are ignored.
+    fn parse(value: &str) -> Option<AuthChallenge> {
+        let value = value.trim();
+        let (scheme, mut rest) = value.split_once([' ', '\t']).unwrap_or((value, ""));
+        if !is_token(scheme) {
+            return None;
+        }
+        let mut params = Vec::new();
+        if is_token68(rest.trim()) {
+            return Some(AuthChallenge { scheme: scheme.to_string(), params });
+        }
+        loop {
+            rest = rest.trim_start_matches([' ', '\t', ',']);
+            if rest.is_empty() {
+                break;
+            }
+            let name_len = rest.find(|c: char| !is_token_char(c)).unwrap_or(rest.len());
+            let (name, after) = rest.split_at(name_len);
+            let after = after.trim_start();
+            if name.is_empty() {
+                return None;
+            }
+            if !after.starts_with('=') {
+                // the scheme of the next challenge.
+                break;
+            }
+            let (param, remaining) = parse_param_value(after[1..].trim_start())?;
+            params.push((name.to_string(), param));
+            rest = remaining;
+        }
+        Some(AuthChallenge { scheme: scheme.to_string(), params })
+    }
+}
+
+/// Parses the value of a `Link` header into `(rel, url)` pairs, resolving relative urls against the
+/// base, if given. Parsing stops at the first malformed link.
+fn parse_links(value: &str, base: Option<&Url>) -> Vec<(String, String)> {
+    let mut links = Vec::new();
+    let mut rest = value;
+    loop {
+        rest = rest.trim_start_matches([' ', '\t', ',']);
+        let target = match rest.strip_prefix('<').and_then(|rest| rest.split_once('>')) {
+            Some((target, after)) => {
+                rest = after;
+                target.trim()
+            }
+            None => return links,
+        };
+        let mut rels = None;
+        loop {
+            rest = rest.trim_start();
+            match rest.strip_prefix(';') {
+                Some(after) => rest = after.trim_start(),
+                None => break,
+            }
+            let name_len = rest.find(|c: char| !is_token_char(c)).unwrap_or(rest.len());
+            let (name, after) = rest.split_at(name_len);
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix('=') {
+                let (param, remaining) = match parse_param_value(after.trim_start()) {
+                    Some(parsed) => parsed,
+                    None => return links,
+                };
+                if name.eq_ignore_ascii_case("rel") && rels.is_none() {
+                    rels = Some(param);
+                }
+                rest = remaining;
+            }
+        }
+        let url = base.and_then(|base| base.join(target).ok())
+            .map_or_else(|| target.to_string(), |url| url.as_absolute().to_string());
+        for rel in rels.unwrap_or_default().split_whitespace() {
+            links.push((rel.to_ascii_lowercase(), url.clone()));
+        }
+    }
+}
+
+/// Parses a parameter value (a token or a quoted string), returning it along with the rest of the input.
+fn parse_param_value(input: &str) -> Option<(String, &str)> {
+    if let Some(quoted) = input.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Some((value, &quoted[i + 1..])),
+                '\\' => value.push(chars.next()?.1),
+                _ => value.push(c),
+            }
+        }
+        return None;
+    }
+    let len = input.find(|c: char| !is_token_char(c)).unwrap_or(input.len());
+    if len == 0 {
+        return None;
+    }
+    Some((input[..len].to_string(), &input[len..]))
+}
+
+fn is_token(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(is_token_char)
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+/// Splits the value of a list header on the commas outside of quoted strings.
+fn split_list(value: &str) -> Vec<&str> {
+    let mut elements = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                elements.push(value[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    elements.push(value[start..].trim());
+    elements.retain(|element| !element.is_empty());
+    elements
+}
+
+/// Checks for the token68 form of data that some schemes send instead of parameters.
+fn is_token68(value: &str) -> bool {
+    let value = value.trim_end_matches('=');
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "-._~+/".contains(c))
+}
+
+/// Creates the Response from its head (as read off the stream) and body, decoding the body as text
+/// according to the mode.
+pub fn new_response_from_parts(head: &[u8], body_bytes: Vec<u8>, url: Url, mode: BodyMode) -> Response {
+    let bytes_received = head.len() + body_bytes.len();
+    let heads = String::from_utf8_lossy(head);
+    let heads = heads.strip_suffix("\r\n\r\n").unwrap_or(&heads);
     let head_lines: Vec<&str> = heads.split("\r\n").collect();
+    let status_line = (*head_lines.first().unwrap()).to_string();
+    let links = head_lines.iter().skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(k, _)| k.eq_ignore_ascii_case("link"))
+        .flat_map(|(_, v)| parse_links(v, Some(&url)))
+        .collect();
     let (resp_state, headers) = process_head_lines(head_lines);
-    let body = (*lines.last().unwrap()).to_string();
+    let is_text = match mode {
+        BodyMode::Auto => {
+            let content_type = find_head_header(heads, "content-type");
+            content_type.is_none_or(is_text_content_type)
+        }
+        BodyMode::Text => true,
+        BodyMode::Bytes => false,
+    };
+    let body = if is_text {
+        String::from_utf8_lossy(&body_bytes).into_owned()
+    } else {
+        String::new()
+    };
     Response {
         status: resp_state,
         body,
+        body_bytes,
         headers,
+        trailers: None,
+        bytes_received,
+        url,
+        status_line,
+        peer_addr: None,
+        peer_certificate: None,
+        raw_exchange: None,
+        links,
+        informational: Vec::new(),
+    }
+}
+
+/// Returns the length of the body as declared by the head of a response.
+///
+/// This is `Some(0)` for responses that never carry a body (204 & 304) and `None` when the length
+/// is not known up front, in which case the body extends until the connection is closed.
+///
+/// An HTTP/1.0 response with a `Transfer-Encoding` has faulty framing, as HTTP/1.0 has no transfer
+/// codings. Its `Content-Length` can't be trusted either, so its length is `None`.
+pub fn get_body_length(head: &[u8]) -> Option<u64> {
+    let head = String::from_utf8_lossy(head);
+    let status = head.split("\r\n").next()?.split(' ').nth(1).map(StatusCode::from_code);
+    if let Some(StatusCode::Success(204)) | Some(StatusCode::Redirection(304)) = status {
+        return Some(0);
+    }
+    if has_faulty_framing(&head) {
+        return None;
+    }
+    find_head_header(&head, "content-length").and_then(|v| v.trim().parse().ok())
+}
+
+/// Checks if any line of the head ends with a `\n` that is not preceded by a `\r`.
+pub fn has_bare_lf(head: &[u8]) -> bool {
+    head.iter().enumerate().any(|(i, byte)| *byte == b'\n' && (i == 0 || head[i - 1] != b'\r'))
+}
+
+/// Checks that the head of a response is well-formed, returning an error of kind `ParseError` if
+/// it isn't.
+///
+/// This rejects a head that is not valid UTF-8, lines ending with a bare `\n` instead of `\r\n`,
+/// a malformed status line, header lines without a colon and heads that make the length of the
+/// body ambiguous.
+pub fn validate_head(head: &[u8]) -> Result<(), NanoGetError> {
+    let parse_error = || NanoGetError::new(ErrorKind::ParseError);
+    if has_bare_lf(head) {
+        return Err(parse_error());
+    }
+    let head = std::str::from_utf8(head).map_err(|_err| parse_error())?;
+    let head = head.strip_suffix("\r\n\r\n").ok_or_else(parse_error)?;
+    let mut lines = head.split("\r\n");
+    let mut status = lines.next().unwrap_or_default().splitn(3, ' ');
+    let version = status.next().unwrap_or_default().strip_prefix("HTTP/").unwrap_or_default().as_bytes();
+    let code = status.next().unwrap_or_default().as_bytes();
+    let valid_version = version.len() == 3 && version[0].is_ascii_digit() && version[1] == b'.' && version[2].is_ascii_digit();
+    if !valid_version || code.len() != 3 || !code.iter().all(u8::is_ascii_digit) {
+        return Err(parse_error());
+    }
+    let mut content_length = None;
+    let mut has_transfer_encoding = false;
+    for line in lines {
+        let (k, v) = line.split_once(':').ok_or_else(parse_error)?;
+        if k.is_empty() || k.contains(|c: char| c.is_ascii_whitespace()) {
+            return Err(parse_error());
+        }
+        if k.eq_ignore_ascii_case("transfer-encoding") {
+            has_transfer_encoding = true;
+        } else if k.eq_ignore_ascii_case("content-length") {
+            let length: u64 = v.trim().parse().map_err(|_err| parse_error())?;
+            if content_length.is_some_and(|previous| previous != length) {
+                return Err(parse_error());
+            }
+            content_length = Some(length);
+        }
+    }
+    if has_transfer_encoding && content_length.is_some() {
+        return Err(parse_error());
+    }
+    Ok(())
+}
+
+/// Checks if the body of a response is sent in chunks, as declared by the head of a response.
+///
+/// This takes precedence over any `Content-Length` the response declares. HTTP/1.0 responses are
+/// never chunked (see `get_body_length`).
+pub fn is_chunked(head: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(head);
+    !has_faulty_framing(&head) && find_head_header(&head, "transfer-encoding")
+        .and_then(|v| v.rsplit(',').next())
+        .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+}
+
+/// Checks if the server intends to keep the connection open after the response, as declared by
+/// the head of a response.
+///
+/// HTTP/1.1 connections are persistent unless the response says `Connection: close`, while HTTP/1.0
+/// connections are only persistent with `Connection: keep-alive`. A connection is never persistent
+/// after a response with faulty framing (see `get_body_length`), as the end of the body is unknown.
+pub fn is_keep_alive(head: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(head);
+    if has_faulty_framing(&head) {
+        return false;
+    }
+    let has_token = |token: &str| {
+        find_head_header(&head, "connection")
+            .is_some_and(|v| v.split(',').any(|t| t.trim().eq_ignore_ascii_case(token)))
+    };
+    if head.starts_with("HTTP/1.0") {
+        has_token("keep-alive")
+    } else {
+        !has_token("close")
+    }
+}
+
+/// Checks if the media type holds text, as described in `Request::body_mode`.
+fn is_text_content_type(content_type: &str) -> bool {
+    const TEXT_TYPES: [&str; 4] = ["application/json", "application/xml", "application/javascript",
+        "application/x-www-form-urlencoded"];
+    let media_type = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    media_type.starts_with("text/") || media_type.ends_with("+json") || media_type.ends_with("+xml")
+        || TEXT_TYPES.contains(&media_type.as_str())
+}
+
+fn has_faulty_framing(head: &str) -> bool {
+    head.starts_with("HTTP/1.0") && find_head_header(head, "transfer-encoding").is_some()
+}
+
+fn find_head_header<'a>(head: &'a str, key: &str) -> Option<&'a str> {
+    head.split("\r\n").skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .find(|(k, _)| k.trim().eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
+
+fn parse_delta_seconds(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
     }
+    value.parse().ok().map(Duration::from_secs)
+}
+
+/// Parses the status from the head of a response.
+pub fn parse_status(head: &[u8]) -> ResponseStatus {
+    let head = String::from_utf8_lossy(head);
+    process_head_lines(head.split("\r\n").collect()).0
 }
 
 fn process_head_lines(lines: Vec<&str>) -> (ResponseStatus, Option<HashMap<String, String>>) {
-    let head = *lines.get(0).unwrap();
-    let parts: Vec<&str> = head.split(' ').collect();
-    let status_code = StatusCode::from_code(parts.get(1).unwrap());
+    let head = *lines.first().unwrap();
+    let parts: Vec<&str> = head.splitn(3, ' ').collect();
+    let status_code = StatusCode::from_code(parts.get(1).unwrap_or(&""));
     let reason = parts.get(2).map(|v| (*v).to_string());
     let response_headers = process_response_headers(&lines[1..]);
     (ResponseStatus(status_code, reason), response_headers)
@@ -154,12 +1153,47 @@ impl StatusCode {
         }
     }
 
+    /// Returns the class of the status code, i.e. its hundreds digit (2 for a 2xx code).
+    ///
+    /// Returns `None` for the internal `Ignore` & `Failure` variants.
+    pub fn category(&self) -> Option<u16> {
+        self.get_code().map(|code| code / 100)
+    }
+
+    /// Checks if the status code is in the 1xx range.
+    pub fn is_informational(&self) -> bool {
+        matches!(self, StatusCode::Informational(_))
+    }
+
+    /// Checks if the status code is in the 2xx range.
+    pub fn is_success(&self) -> bool {
+        matches!(self, StatusCode::Success(_))
+    }
+
+    /// Checks if the status code is in the 3xx range.
+    pub fn is_redirect(&self) -> bool {
+        matches!(self, StatusCode::Redirection(_))
+    }
+
+    /// Checks if the status code is in the 4xx range.
+    pub fn is_client_error(&self) -> bool {
+        matches!(self, StatusCode::ClientError(_))
+    }
+
+    /// Checks if the status code is in the 5xx range.
+    pub fn is_server_error(&self) -> bool {
+        matches!(self, StatusCode::ServerError(_))
+    }
+
     fn from_code(code: &str) -> Self {
         let code = code.trim();
         if code.len() != 3 {
             return StatusCode::Failure;
         }
-        let code_num: u16 = code.parse().unwrap();
+        let code_num: u16 = match code.parse() {
+            Ok(code_num) => code_num,
+            Err(_) => return StatusCode::Failure,
+        };
         match code_num {
             100..=199 => StatusCode::Informational(code_num),
             200..=299 => StatusCode::Success(code_num),
@@ -169,4 +1203,236 @@ impl StatusCode {
             _ => StatusCode::Failure,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response_from(raw: &str) -> Response {
+        let (head, body) = raw.split_at(raw.find("\r\n\r\n").map_or(raw.len(), |i| i + 4));
+        new_response_from_parts(head.as_bytes(), body.as_bytes().to_vec(), Url::new("http://example.com/"), BodyMode::Auto)
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let response = response_from("HTTP/1.1 429 Too Many Requests\r\nRetry-After: 120\r\n\r\n");
+        assert_eq!(response.retry_after(), Some(Duration::from_secs(120)));
+        let response = response_from("HTTP/1.1 503 Service Unavailable\r\nRetry-After: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n");
+        assert_eq!(response.retry_after(), Some(Duration::from_secs(0)));
+        let response = response_from("HTTP/1.1 503 Service Unavailable\r\nRetry-After: Fri, 31 Dec 9999 23:59:59 GMT\r\n\r\n");
+        assert!(response.retry_after().unwrap() > Duration::from_secs(86400));
+        for value in &["soon", "-5", "1.5", "99999999999999999999999"] {
+            let response = response_from(&format!("HTTP/1.1 429 Too Many Requests\r\nRetry-After: {}\r\n\r\n", value));
+            assert_eq!(response.retry_after(), None, "{}", value);
+        }
+        assert_eq!(response_from("HTTP/1.1 200 OK\r\n\r\n").retry_after(), None);
+    }
+
+    #[test]
+    fn test_end_to_end_headers() {
+        let response = response_from("HTTP/1.1 200 OK\r\nConnection: close, X-Internal\r\nX-Internal: 1\r\n\
+            Keep-Alive: timeout=5\r\nTransfer-Encoding: chunked\r\nContent-Type: text/plain\r\n\r\n");
+        let headers: Vec<(&str, &str)> = response.end_to_end_headers().collect();
+        assert_eq!(headers, vec![("Content-Type", "text/plain")]);
+    }
+
+    #[test]
+    fn test_transfer_encoding() {
+        let response = response_from("HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, Chunked\r\n\r\n");
+        assert_eq!(response.transfer_encoding(), Some("gzip, Chunked"));
+        assert!(response.is_chunked());
+        let response = response_from("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked, gzip\r\n\r\n");
+        assert!(!response.is_chunked());
+        let response = response_from("HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+        assert_eq!(response.transfer_encoding(), None);
+        assert!(!response.is_chunked());
+    }
+
+    #[test]
+    fn test_header_values() {
+        let response = response_from("HTTP/1.1 200 OK\r\nCache-Control: private=\"Set-Cookie, X-Id\", no-cache,, max-age=0\r\n\
+            Vary: Accept\r\nX-Quoted: \"a\\\"b, c\", d\r\n\r\n");
+        assert_eq!(response.header_values("cache-control"), vec!["private=\"Set-Cookie, X-Id\"", "no-cache", "max-age=0"]);
+        assert_eq!(response.header_values("Vary"), vec!["Accept"]);
+        assert_eq!(response.header_values("x-quoted"), vec!["\"a\\\"b, c\"", "d"]);
+        assert!(response.header_values("accept").is_empty());
+    }
+
+    #[test]
+    fn test_looks_gzipped() {
+        let gzipped = |head: &str| {
+            let mut response = response_from(head);
+            response.body_bytes = vec![0x1f, 0x8b, 0x08, 0x00];
+            response.looks_gzipped()
+        };
+        assert!(gzipped("HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n"));
+        assert!(!gzipped("HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\n\r\n"));
+        assert!(!response_from("HTTP/1.1 200 OK\r\n\r\n\u{1f}").looks_gzipped());
+    }
+
+    #[test]
+    fn test_is_cacheable() {
+        assert!(response_from("HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").is_cacheable());
+        assert!(response_from("HTTP/1.1 200 OK\r\nCache-Control: public, max-age=60\r\n\r\n").is_cacheable());
+        assert!(!response_from("HTTP/1.1 200 OK\r\nCache-Control: no-store\r\n\r\n").is_cacheable());
+        assert!(!response_from("HTTP/1.1 200 OK\r\nCache-Control: max-age=0\r\n\r\n").is_cacheable());
+        assert!(!response_from("HTTP/1.1 200 OK\r\nCache-Control: No-Cache\r\n\r\n").is_cacheable());
+        assert!(!response_from("HTTP/1.1 200 OK\r\nCache-Control: private=\"Set-Cookie\"\r\n\r\n").is_cacheable());
+        assert!(response_from("HTTP/1.1 200 OK\r\nCache-Control: max-age=0, s-maxage=60\r\n\r\n").is_cacheable());
+        assert!(!response_from("HTTP/1.1 500 Internal Server Error\r\n\r\n").is_cacheable());
+        assert!(response_from("HTTP/1.1 500 Internal Server Error\r\nCache-Control: max-age=5\r\n\r\n").is_cacheable());
+        assert!(response_from("HTTP/1.1 302 Found\r\nExpires: Fri, 31 Dec 9999 23:59:59 GMT\r\n\r\n").is_cacheable());
+        assert!(!response_from("HTTP/1.1 200 OK\r\nCache-Control: max-age=0\r\nExpires: Fri, 31 Dec 9999 23:59:59 GMT\r\n\r\n")
+            .is_cacheable());
+        assert!(!response_from("HTTP/1.1 100 Continue\r\n\r\n").is_cacheable());
+    }
+
+    #[test]
+    fn test_auth_challenge() {
+        let response = response_from("HTTP/1.1 401 Unauthorized\r\n\
+            WWW-Authenticate: Digest realm=\"a, \\\"b\\\"\", qop=auth,nonce=\"xyz\", Basic realm=\"c\"\r\n\r\n");
+        let challenge = response.auth_challenge().unwrap();
+        assert_eq!(challenge.scheme, "Digest");
+        assert_eq!(challenge.params, vec![("realm".to_string(), "a, \"b\"".to_string()),
+                                          ("qop".to_string(), "auth".to_string()),
+                                          ("nonce".to_string(), "xyz".to_string())]);
+        assert_eq!(challenge.param("QOP"), Some("auth"));
+        let challenge = response_from("HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Basic\r\n\r\n")
+            .auth_challenge().unwrap();
+        assert_eq!((challenge.scheme.as_str(), challenge.params.len()), ("Basic", 0));
+        let challenge = response_from("HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Negotiate abc+/==\r\n\r\n")
+            .auth_challenge().unwrap();
+        assert_eq!((challenge.scheme.as_str(), challenge.params.len()), ("Negotiate", 0));
+        for value in &["", "Basic realm=\"unterminated", "Bearer error=, realm=x", "Basic @realm=x", "b@d realm=x"] {
+            let response = response_from(&format!("HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: {}\r\n\r\n", value));
+            assert_eq!(response.auth_challenge(), None, "{}", value);
+        }
+        assert_eq!(response_from("HTTP/1.1 200 OK\r\n\r\n").www_authenticate(), None);
+    }
+
+    #[test]
+    fn test_date_and_age() {
+        let response = response_from("HTTP/1.1 200 OK\r\nDate: Sun, 06 Nov 1994 08:49:37 GMT\r\nAge: 42\r\n\r\n");
+        assert_eq!(response.date(), Some(std::time::UNIX_EPOCH + Duration::from_secs(784111777)));
+        assert_eq!(response.age(), Some(Duration::from_secs(42)));
+        let response = response_from("HTTP/1.1 200 OK\r\nDate: someday\r\nAge: -1\r\n\r\n");
+        assert_eq!(response.date(), None);
+        assert_eq!(response.age(), None);
+    }
+
+    #[test]
+    fn test_from_parts() {
+        let mut headers = HashMap::new();
+        headers.insert("Location".to_string(), "/next".to_string());
+        let status = ResponseStatus(StatusCode::Redirection(302), Some("Found".to_string()));
+        let response = Response::from_parts(status, headers, vec![0xff, 0x00]);
+        assert_eq!(response.status_line(), "HTTP/1.1 302 Found");
+        assert_eq!(response.http_version(), "HTTP/1.1");
+        assert_eq!(response.location(), Some("/next"));
+        let base = Url::new("https://example.com:8443/a/b");
+        assert_eq!(response.location_url(&base).unwrap().unwrap().normalized(), "https://example.com:8443/next");
+        assert_eq!(response.body_bytes(), &[0xff, 0x00]);
+        assert_eq!(response.body, "\u{fffd}\u{0}");
+    }
+
+    #[test]
+    fn test_status_code_category() {
+        let codes = [("100", 1), ("204", 2), ("301", 3), ("404", 4), ("503", 5)];
+        for &(code, category) in codes.iter() {
+            let status = StatusCode::from_code(code);
+            assert_eq!(status.category(), Some(category));
+            let checks = [status.is_informational(), status.is_success(), status.is_redirect(),
+                status.is_client_error(), status.is_server_error()];
+            let expected: Vec<bool> = (1..=5).map(|c| c == category).collect();
+            assert_eq!(checks.to_vec(), expected, "{}", code);
+        }
+        assert_eq!(StatusCode::from_code("999").category(), None);
+        assert!(!StatusCode::Failure.is_success());
+    }
+
+    #[test]
+    fn test_multipart_parts() {
+        let mut raw = b"HTTP/1.1 200 OK\r\nContent-Type: multipart/mixed; charset=utf-8; boundary=\"a:b c\"\r\n\r\n\
+            preamble\r\n--a:b c\r\nContent-Type: text/plain\r\nX-Id: 1\r\n\r\nfirst\r\n--a:b c  \r\n\r\n".to_vec();
+        raw.extend_from_slice(&[0xff, 0x00, b'\r', b'\n', 0xfe]);
+        raw.extend_from_slice(b"\r\n--a:b c--\r\nepilogue");
+        let (head, body) = raw.split_at(raw.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4);
+        let response = new_response_from_parts(head, body.to_vec(), Url::new("http://example.com/"), BodyMode::Auto);
+        let parts = response.multipart_parts().unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].headers, vec![("Content-Type".to_string(), "text/plain".to_string()),
+                                          ("X-Id".to_string(), "1".to_string())]);
+        assert_eq!(parts[0].header("x-id"), Some("1"));
+        assert_eq!(parts[0].body, b"first");
+        assert!(parts[1].headers.is_empty());
+        assert_eq!(parts[1].body, [0xff, 0x00, b'\r', b'\n', 0xfe]);
+
+        let empty = response_from("HTTP/1.1 200 OK\r\nContent-Type: multipart/form-data; boundary=x\r\n\r\n--x--\r\n");
+        assert!(empty.multipart_parts().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_multipart_parts_errors() {
+        let invalid = [
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; boundary=x\r\n\r\n--x\r\n\r\na\r\n--x--",
+            "HTTP/1.1 200 OK\r\nContent-Type: multipart/mixed\r\n\r\n--x\r\n\r\na\r\n--x--",
+            "HTTP/1.1 200 OK\r\nContent-Type: multipart/mixed; boundary=\r\n\r\n--\r\n\r\na\r\n----",
+            "HTTP/1.1 200 OK\r\nContent-Type: multipart/mixed; boundary=\"x \"\r\n\r\n--x \r\n\r\na\r\n--x --",
+            "HTTP/1.1 200 OK\r\nContent-Type: multipart/mixed; boundary=x\r\n\r\n--x\r\n\r\ncut short",
+            "HTTP/1.1 200 OK\r\nContent-Type: multipart/mixed; boundary=x\r\n\r\nno boundary at all",
+            "HTTP/1.1 200 OK\r\nContent-Type: multipart/mixed; boundary=x\r\n\r\n--x\r\nNo-Blank-Line: 1\r\n--x--",
+            "HTTP/1.1 200 OK\r\n\r\n--x\r\n\r\na\r\n--x--",
+        ];
+        for raw in invalid.iter() {
+            let err = response_from(raw).multipart_parts().unwrap_err();
+            assert_eq!(err.kind(), &ErrorKind::ParseError, "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn test_content_length_and_body_len() {
+        let response = response_from("HTTP/1.1 200 OK\r\nContent-Length:  3\r\n\r\nabc");
+        assert_eq!((response.content_length(), response.body_len()), (Some(3), 3));
+        let response = response_from("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nabc");
+        assert_eq!((response.content_length(), response.body_len()), (None, 3));
+        assert_eq!(response_from("HTTP/1.1 200 OK\r\nContent-Length: x\r\n\r\n").content_length(), None);
+    }
+
+    #[test]
+    fn test_validate_head_accepts_lengths_over_4_gib() {
+        assert!(validate_head(b"HTTP/1.1 200 OK\r\nContent-Length: 5000000000\r\n\r\n").is_ok());
+        let duplicate = b"HTTP/1.1 200 OK\r\nContent-Length: 5000000000\r\nContent-Length: 705032704\r\n\r\n";
+        assert_eq!(validate_head(duplicate).unwrap_err().kind(), &ErrorKind::ParseError);
+    }
+
+    #[test]
+    fn test_links() {
+        let response = response_from("HTTP/1.1 200 OK\r\n\
+            Link: <https://api.example.com/items?page=2&sort=a,b>; rel=\"next\", </items?page=9>; title=\"x;y\"; rel=\"Last\"\r\n\
+            Link: <../prev>; rel=\"prev first\"; rel=ignored, <no-rel>; title=a\r\n\
+            Link: <https://example.com/other>; rel=next\r\n\r\n");
+        let links: Vec<(&str, &str)> = response.links().iter().map(|(rel, url)| (rel.as_str(), url.as_str())).collect();
+        assert_eq!(links, vec![
+            ("next", "https://api.example.com/items?page=2&sort=a,b"),
+            ("last", "http://example.com/items?page=9"),
+            ("prev", "http://example.com/prev"),
+            ("first", "http://example.com/prev"),
+            ("next", "https://example.com/other"),
+        ]);
+        assert_eq!(response.next_link(), Some("https://api.example.com/items?page=2&sort=a,b"));
+
+        let response = response_from("HTTP/1.1 200 OK\r\nLink: <a>; rel=prev, garbage, <b>; rel=next\r\n\r\n");
+        assert_eq!(response.links().len(), 1);
+        assert_eq!(response.next_link(), None);
+        assert!(response_from("HTTP/1.1 200 OK\r\n\r\n").links().is_empty());
+    }
+
+    #[test]
+    fn test_header_names() {
+        let response = response_from("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nX-Trace: 1\r\nx-trace: 2\r\n\r\n");
+        let mut names: Vec<String> = response.header_names().map(|name| name.to_ascii_lowercase()).collect();
+        names.sort();
+        assert_eq!(names, vec!["content-type", "x-trace"]);
+    }
+}
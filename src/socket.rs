@@ -0,0 +1,204 @@
+//! This module provides the `SocketConfig`, which tunes the sockets that requests connect with.
+use std::io;
+#[cfg(feature = "socket-config")]
+use std::net::IpAddr;
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+#[cfg(feature = "socket-config")]
+use socket2::{Domain, Protocol, SockRef, Socket, TcpKeepalive, Type};
+
+/// Low-level options for the socket of a connection, for tuning latency and throughput.
+///
+/// Any option that is not set is left to the operating system's default. Only `nodelay` is
+/// available by default. The other options depend on the [socket2](https://crates.io/crates/socket2)
+/// crate, and are enabled by the "socket-config" feature flag (which is NOT activated by default).
+///
+/// Set on a `Request` with `Request::socket_config`, or on a `Client` for all its requests. Like
+/// the timeouts, the options set on the request take precedence over those of the client.
+///
+/// ## Example
+/// ```rust,no_run
+/// use nano_get::{Request, SocketConfig};
+///
+/// let mut config = SocketConfig::default();
+/// config.nodelay(true);
+/// let mut request = Request::default_get_request("http://example.com/").unwrap();
+/// request.socket_config(config);
+/// let response = request.execute().unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SocketConfig {
+    nodelay: Option<bool>,
+    #[cfg(feature = "socket-config")]
+    keepalive: Option<bool>,
+    #[cfg(feature = "socket-config")]
+    keepalive_interval: Option<Duration>,
+    #[cfg(feature = "socket-config")]
+    recv_buffer_size: Option<usize>,
+    #[cfg(feature = "socket-config")]
+    send_buffer_size: Option<usize>,
+    #[cfg(feature = "socket-config")]
+    source_addr: Option<IpAddr>,
+}
+
+impl SocketConfig {
+    /// Sets whether Nagle's algorithm is disabled (`TCP_NODELAY`), so that small writes are sent
+    /// right away instead of being held back to be combined.
+    pub fn nodelay(&mut self, nodelay: bool) {
+        self.nodelay = Some(nodelay);
+    }
+
+    /// Sets whether TCP keep-alive probes are sent on an idle connection (`SO_KEEPALIVE`).
+    #[cfg(feature = "socket-config")]
+    pub fn keepalive(&mut self, keepalive: bool) {
+        self.keepalive = Some(keepalive);
+    }
+
+    /// Sets how long a connection is idle before the first keep-alive probe, and the time between
+    /// probes where the platform supports setting it. This turns keep-alive on, unless `keepalive`
+    /// is set to `false`.
+    #[cfg(feature = "socket-config")]
+    pub fn keepalive_interval(&mut self, interval: Duration) {
+        self.keepalive_interval = Some(interval);
+    }
+
+    /// Sets the size of the receive buffer of the socket (`SO_RCVBUF`), set before connecting.
+    #[cfg(feature = "socket-config")]
+    pub fn recv_buffer_size(&mut self, size: usize) {
+        self.recv_buffer_size = Some(size);
+    }
+
+    /// Sets the size of the send buffer of the socket (`SO_SNDBUF`), set before connecting.
+    #[cfg(feature = "socket-config")]
+    pub fn send_buffer_size(&mut self, size: usize) {
+        self.send_buffer_size = Some(size);
+    }
+
+    /// Sets the local address to connect from, like one of several network interfaces. The port is
+    /// chosen by the operating system. Connecting to an address of the other IP version fails.
+    #[cfg(feature = "socket-config")]
+    pub fn source_addr(&mut self, addr: IpAddr) {
+        self.source_addr = Some(addr);
+    }
+
+    /// Fills in the options that are not set with the given defaults.
+    pub(crate) fn or(&self, defaults: &SocketConfig) -> SocketConfig {
+        SocketConfig {
+            nodelay: self.nodelay.or(defaults.nodelay),
+            #[cfg(feature = "socket-config")]
+            keepalive: self.keepalive.or(defaults.keepalive),
+            #[cfg(feature = "socket-config")]
+            keepalive_interval: self.keepalive_interval.or(defaults.keepalive_interval),
+            #[cfg(feature = "socket-config")]
+            recv_buffer_size: self.recv_buffer_size.or(defaults.recv_buffer_size),
+            #[cfg(feature = "socket-config")]
+            send_buffer_size: self.send_buffer_size.or(defaults.send_buffer_size),
+            #[cfg(feature = "socket-config")]
+            source_addr: self.source_addr.or(defaults.source_addr),
+        }
+    }
+}
+
+/// Connects to the address, within the timeout if given, applying the options of the config.
+pub(crate) fn connect(addr: &SocketAddr, timeout: Option<Duration>, config: &SocketConfig) -> io::Result<TcpStream> {
+    let stream = open(addr, timeout, config)?;
+    if let Some(nodelay) = config.nodelay {
+        stream.set_nodelay(nodelay)?;
+    }
+    #[cfg(feature = "socket-config")] {
+        let socket = SockRef::from(&stream);
+        match (config.keepalive, config.keepalive_interval) {
+            (Some(false), _) => socket.set_keepalive(false)?,
+            (_, Some(interval)) => socket.set_tcp_keepalive(&keepalive_every(interval))?,
+            (Some(true), None) => socket.set_keepalive(true)?,
+            (None, None) => {}
+        }
+    }
+    Ok(stream)
+}
+
+#[cfg(not(feature = "socket-config"))]
+fn open(addr: &SocketAddr, timeout: Option<Duration>, _config: &SocketConfig) -> io::Result<TcpStream> {
+    match timeout {
+        Some(timeout) => TcpStream::connect_timeout(addr, timeout),
+        None => TcpStream::connect(addr),
+    }
+}
+
+/// Opens the connection, setting the options that must be set before connecting.
+#[cfg(feature = "socket-config")]
+fn open(addr: &SocketAddr, timeout: Option<Duration>, config: &SocketConfig) -> io::Result<TcpStream> {
+    let socket = Socket::new(Domain::for_address(*addr), Type::STREAM, Some(Protocol::TCP))?;
+    if let Some(size) = config.recv_buffer_size {
+        socket.set_recv_buffer_size(size)?;
+    }
+    if let Some(size) = config.send_buffer_size {
+        socket.set_send_buffer_size(size)?;
+    }
+    if let Some(source) = config.source_addr {
+        socket.bind(&SocketAddr::new(source, 0).into())?;
+    }
+    match timeout {
+        Some(timeout) => socket.connect_timeout(&(*addr).into(), timeout)?,
+        None => socket.connect(&(*addr).into())?,
+    }
+    Ok(socket.into())
+}
+
+#[cfg(feature = "socket-config")]
+fn keepalive_every(interval: Duration) -> TcpKeepalive {
+    let keepalive = TcpKeepalive::new().with_time(interval);
+    #[cfg(any(windows, target_os = "linux", target_os = "android", target_vendor = "apple", target_os = "freebsd",
+              target_os = "netbsd", target_os = "dragonfly", target_os = "fuchsia", target_os = "illumos"))]
+    let keepalive = keepalive.with_interval(interval);
+    keepalive
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn test_config_is_applied() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stream = connect(&addr, None, &SocketConfig::default()).unwrap();
+        assert!(!stream.nodelay().unwrap());
+        let mut config = SocketConfig::default();
+        config.nodelay(true);
+        let stream = connect(&addr, Some(Duration::from_secs(1)), &config).unwrap();
+        assert!(stream.nodelay().unwrap());
+    }
+
+    #[cfg(feature = "socket-config")]
+    #[test]
+    fn test_socket2_options_are_applied() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut config = SocketConfig::default();
+        config.keepalive_interval(Duration::from_secs(30));
+        config.recv_buffer_size(64 * 1024);
+        config.source_addr([127, 0, 0, 1].into());
+        let stream = connect(&addr, None, &config).unwrap();
+        let socket = SockRef::from(&stream);
+        assert!(socket.keepalive().unwrap());
+        assert!(socket.recv_buffer_size().unwrap() >= 64 * 1024);
+        assert_eq!(stream.local_addr().unwrap().ip(), IpAddr::from([127, 0, 0, 1]));
+        let mut config = SocketConfig::default();
+        config.source_addr("::1".parse().unwrap());
+        assert!(connect(&addr, None, &config).is_err());
+    }
+
+    #[test]
+    fn test_request_options_take_precedence() {
+        let mut request = SocketConfig::default();
+        request.nodelay(false);
+        let mut client = SocketConfig::default();
+        client.nodelay(true);
+        assert_eq!(request.or(&client).nodelay, Some(false));
+        assert_eq!(SocketConfig::default().or(&client).nodelay, Some(true));
+    }
+}
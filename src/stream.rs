@@ -0,0 +1,192 @@
+//! This module provides reading the body of a response as it arrives, instead of all at once.
+use std::io::{self, BufRead, BufReader, Read};
+
+use super::errors::{ErrorKind, NanoGetError};
+use super::http::{io_error, parse_chunk_size};
+use super::response::{get_body_length, is_chunked, parse_status, ResponseStatus};
+
+/// An iterator over the lines of the body of a response, which are read off the connection as they
+/// arrive. See `Request::execute_lines`.
+///
+/// A chunked body is decoded on the fly, so a line may span any number of chunks. Each line is
+/// yielded without its line ending (`\n` or `\r\n`). A line that is not valid UTF-8 is an error of
+/// kind `ParseError`.
+pub struct ResponseLines {
+    status: ResponseStatus,
+    reader: BufReader<BodyStream>,
+}
+
+impl ResponseLines {
+    /// Creates the iterator from the head of the response and the connection, positioned right
+    /// after the head.
+    pub(crate) fn new(head: &[u8], reader: BufReader<Box<dyn Read + Send>>) -> ResponseLines {
+        let framing = if is_chunked(head) {
+            Framing::Chunked { remaining: 0, started: false, done: false }
+        } else {
+            match get_body_length(head) {
+                Some(length) => Framing::Length(length),
+                None => Framing::UntilClose,
+            }
+        };
+        ResponseLines {
+            status: parse_status(head),
+            reader: BufReader::new(BodyStream { reader, framing }),
+        }
+    }
+
+    /// Returns the status of the response.
+    pub fn status(&self) -> &ResponseStatus {
+        &self.status
+    }
+}
+
+impl Iterator for ResponseLines {
+    type Item = Result<String, NanoGetError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = Vec::new();
+        match self.reader.read_until(b'\n', &mut line) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(err) => return Some(Err(io_error(err))),
+        }
+        if line.ends_with(b"\n") {
+            line.pop();
+            if line.ends_with(b"\r") {
+                line.pop();
+            }
+        }
+        Some(String::from_utf8(line).map_err(|_err| NanoGetError::new(ErrorKind::ParseError)))
+    }
+}
+
+/// How the end of the body is found.
+enum Framing {
+    /// The number of bytes of the body that are left.
+    Length(u64),
+    /// The number of bytes left in the current chunk, whether a chunk has been read yet, and
+    /// whether the last chunk (and the trailers after it) have been read.
+    Chunked { remaining: u64, started: bool, done: bool },
+    /// The body ends when the connection is closed.
+    UntilClose,
+}
+
+/// The body of a response, read (and decoded, if chunked) off the connection on demand.
+struct BodyStream {
+    reader: BufReader<Box<dyn Read + Send>>,
+    framing: Framing,
+}
+
+impl Read for BodyStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.framing {
+            Framing::Length(ref mut remaining) => {
+                let read = self.reader.by_ref().take(*remaining).read(buf)?;
+                *remaining -= read as u64;
+                Ok(read)
+            }
+            Framing::Chunked { ref mut remaining, ref mut started, ref mut done } => {
+                if *done {
+                    return Ok(0);
+                }
+                if *remaining == 0 {
+                    if *started {
+                        read_line(&mut self.reader)?;
+                    }
+                    *started = true;
+                    let size = parse_chunk_size(&read_line(&mut self.reader)?).map_err(invalid_data)?;
+                    if size == 0 {
+                        *done = true;
+                        while !read_line(&mut self.reader)?.iter().all(|b| *b == b'\r' || *b == b'\n') {}
+                        return Ok(0);
+                    }
+                    *remaining = size;
+                }
+                let read = self.reader.by_ref().take(*remaining).read(buf)?;
+                if read == 0 {
+                    // the connection was closed in the middle of a chunk.
+                    *done = true;
+                }
+                *remaining -= read as u64;
+                Ok(read)
+            }
+            Framing::UntilClose => self.reader.read(buf),
+        }
+    }
+}
+
+/// Reads a line of the chunked framing, which is empty once the connection is closed.
+fn read_line(reader: &mut dyn BufRead) -> io::Result<Vec<u8>> {
+    let mut line = Vec::new();
+    reader.read_until(b'\n', &mut line)?;
+    Ok(line)
+}
+
+fn invalid_data(err: NanoGetError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Yields a single byte for each read, like a slow connection.
+    struct OneByteReader(&'static [u8]);
+
+    impl Read for OneByteReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.0.len().min(buf.len()).min(1);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    fn lines_from(raw: &'static [u8]) -> ResponseLines {
+        lines_from_reader(Box::new(raw))
+    }
+
+    fn lines_from_reader(raw: Box<dyn Read + Send>) -> ResponseLines {
+        let mut reader: BufReader<Box<dyn Read + Send>> = BufReader::new(raw);
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            reader.read_until(b'\n', &mut head).unwrap();
+        }
+        ResponseLines::new(&head, reader)
+    }
+
+    #[test]
+    fn test_lines_across_chunks() {
+        let lines = lines_from(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+            6\r\n{\"a\":1\r\n7\r\n}\n{\"b\":\r\n5;x=y\r\n2}\r\n{\r\n3\r\n}\r\n\r\n0\r\nX-Trailer: 1\r\n\r\n");
+        assert_eq!(lines.status().0.get_code(), Some(200));
+        let lines: Vec<String> = lines.map(Result::unwrap).collect();
+        assert_eq!(lines, vec!["{\"a\":1}", "{\"b\":2}", "{}"]);
+    }
+
+    #[test]
+    fn test_lines_one_byte_at_a_time() {
+        let lines = lines_from_reader(Box::new(OneByteReader(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+            1a\r\nabcdefghijklm\nnopqrstuvwxy\r\n2\r\nz\n\r\n0\r\n\r\n")));
+        let lines: Vec<String> = lines.map(Result::unwrap).collect();
+        assert_eq!(lines, vec!["abcdefghijklm", "nopqrstuvwxyz"]);
+    }
+
+    #[test]
+    fn test_lines_with_length_and_until_close() {
+        let lines: Vec<String> = lines_from(b"HTTP/1.1 200 OK\r\nContent-Length: 8\r\n\r\none\ntwo\nignored")
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(lines, vec!["one", "two"]);
+        let lines: Vec<String> = lines_from(b"HTTP/1.0 200 OK\r\n\r\none\r\nlast")
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(lines, vec!["one", "last"]);
+    }
+
+    #[test]
+    fn test_lines_with_bad_chunk_size() {
+        let mut lines = lines_from(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\nabc\r\n");
+        assert_eq!(lines.next().unwrap().unwrap_err().kind(), &ErrorKind::ParseError);
+    }
+}
@@ -0,0 +1,41 @@
+//! This module provides the local servers that the tests send their requests to.
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+
+/// Serves `connections` connections one at a time, reading the head of the request on each and
+/// then passing the stream, a reader positioned after the head, and the head to `handle`.
+///
+/// Returns the port, and a handle to the results of `handle` for each connection.
+pub(crate) fn serve_with<F, T>(connections: usize, mut handle: F) -> (u16, JoinHandle<Vec<T>>)
+    where F: FnMut(TcpStream, BufReader<TcpStream>, String) -> T + Send + 'static, T: Send + 'static {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = thread::spawn(move || {
+        (0..connections).map(|_| {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut head = String::new();
+            while reader.read_line(&mut head).unwrap() > 0 && !head.ends_with("\r\n\r\n") {}
+            handle(stream, reader, head)
+        }).collect()
+    });
+    (port, server)
+}
+
+/// Serves one connection for each of the given responses, returning the port and a handle to
+/// the requests that were received (their heads, followed by any body with a `Content-Length`).
+pub(crate) fn serve(responses: Vec<&'static [u8]>) -> (u16, JoinHandle<Vec<String>>) {
+    let mut responses = responses.into_iter();
+    serve_with(responses.len(), move |mut stream, mut reader, mut head| {
+        let length = head.lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+            .map_or(0, |(_, v)| v.trim().parse().unwrap());
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        head.push_str(&String::from_utf8(body).unwrap());
+        stream.write_all(responses.next().unwrap()).unwrap();
+        head
+    })
+}
@@ -3,13 +3,11 @@ pub use self::models::{ToUrl, Tuple, Url};
 pub mod models;
 
 pub fn parse_proto(s: String, default_proto: Option<String>) -> (String, String) {
-    let parts: Vec<&str> = s.split("://").collect();
-    if parts.len() > 1 {
-        ((*parts.first().unwrap()).to_string(), parts[1].to_string())
-    } else {
-        match default_proto {
-            Some(proto) => (proto, parts[0].to_string()),
-            None => ("http".to_string(), parts[0].to_string())
+    match s.split_once("://") {
+        Some((proto, rest)) if !proto.contains(['/', '?', '#']) => (proto.to_string(), rest.to_string()),
+        _ => match default_proto {
+            Some(proto) => (proto, s),
+            None => ("http".to_string(), s)
         }
     }
 }
@@ -28,6 +26,14 @@ pub fn parse_full_domain(s: String, default_path: Option<String>) -> (String, St
 }
 
 pub fn parse_host_and_port(s: String, default_port: Option<String>) -> (String, String) {
+    if s.starts_with('[') {
+        if let Some(i) = s.find(']') {
+            if let Some(port) = s[i + 1..].strip_prefix(':') {
+                return (s[..=i].to_string(), port.to_string());
+            }
+            return (s[..=i].to_string(), default_port.unwrap_or_else(|| "80".to_string()));
+        }
+    }
     if s.find(':').is_some() {
         let tuple: Tuple<&str> = s.splitn(2, ':').collect();
         (tuple.left.to_string(), tuple.right.to_string())
@@ -37,4 +43,102 @@ pub fn parse_host_and_port(s: String, default_port: Option<String>) -> (String,
             None => (s, "80".to_string())
         }
     }
-}
\ No newline at end of file
+}
+/// Removes the `.` and `..` segments from the path (keeping any query as is).
+pub fn remove_dot_segments(s: &str) -> String {
+    let (path, query) = match s.find('?') {
+        Some(i) => (&s[..i], &s[i..]),
+        None => (s, ""),
+    };
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/').skip(1) {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    if path.ends_with("/.") || path.ends_with("/..") {
+        segments.push("");
+    }
+    "/".to_string() + &segments.join("/") + query
+}
+
+/// Percent-encodes a key or value for the query of a url, leaving only the unreserved characters
+/// of [RFC 3986](https://tools.ietf.org/html/rfc3986#section-2.3) (`A-Z a-z 0-9 - . _ ~`) as is.
+///
+/// Spaces are encoded as `%20` and the hex digits are uppercase, so the output is stable.
+/// This is what `Url::add_query_param` uses.
+///
+/// ## Example
+/// ```rust
+/// assert_eq!(nano_get::encode_query_component("a b+c"), "a%20b%2Bc");
+/// ```
+pub fn encode_query_component(s: &str) -> String {
+    encode(s, false)
+}
+
+/// Encodes a key or value for an `application/x-www-form-urlencoded` body.
+///
+/// This is like `encode_query_component`, except that spaces are encoded as `+` (so a literal `+`
+/// must be, and is, encoded as `%2B`). Use this for form bodies only, as servers decode a `+` in the
+/// query of a url as a literal plus sign.
+///
+/// ## Example
+/// ```rust
+/// assert_eq!(nano_get::encode_form_component("a b+c"), "a+b%2Bc");
+/// ```
+pub fn encode_form_component(s: &str) -> String {
+    encode(s, true)
+}
+
+/// Encodes a path, keeping the `/` separators, the characters allowed in a path and any existing
+/// percent-encoded bytes as is. This is what `Url::set_path` uses.
+pub(crate) fn encode_path(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@/%".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+
+fn encode(s: &str, space_as_plus: bool) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            encoded.push(b as char);
+        } else if b == b' ' && space_as_plus {
+            encoded.push('+');
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+
+/// Decodes the percent-encoded bytes in the string. Malformed escapes are kept as is.
+pub fn decode_component(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match hex.filter(|_| bytes[i] == b'%').and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(b) => {
+                decoded.push(b);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
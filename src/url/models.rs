@@ -2,7 +2,10 @@ use std::fmt::{Display, Error, Formatter};
 use std::io;
 use std::iter::{FromIterator, IntoIterator};
 
-use super::{parse_full_domain, parse_host_and_port, parse_proto};
+use crate::errors::{ErrorKind, NanoGetError};
+
+use super::{decode_component, encode_path, encode_query_component, parse_full_domain, parse_host_and_port, parse_proto,
+            remove_dot_segments};
 
 /// This is used to represent the various parts of a URL.
 #[derive(Debug, Clone)]
@@ -30,7 +33,7 @@ impl Url {
         let url = url.to_string();
         let (protocol, rest) = parse_proto(url.clone(), None);
         let (full_domain, path) = parse_full_domain(rest, None);
-        let (host, port) = parse_host_and_port(full_domain, Self::get_default_port_for_proto(&protocol));
+        let (host, port) = parse_host_and_port(full_domain, Self::get_default_port_for_proto(&protocol.to_ascii_lowercase()));
         Url {
             protocol,
             host,
@@ -40,6 +43,122 @@ impl Url {
         }
     }
 
+    /// Parses the url, returning an error of kind `ParseError` if it is not valid.
+    ///
+    /// Unlike `Url::new`, this checks that the url has no whitespace or control characters,
+    /// a valid scheme, a non-empty host and a numeric port.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::Url;
+    /// assert!(Url::parse("http://example.com:8080/").is_ok());
+    /// assert!(Url::parse("http://example.com:port/").is_err());
+    /// ```
+    pub fn parse(url: &str) -> Result<Url, NanoGetError> {
+        if url.is_empty() || url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(NanoGetError::new(ErrorKind::ParseError));
+        }
+        let parsed = Url::new(url);
+        let valid_scheme = parsed.protocol.starts_with(|c: char| c.is_ascii_alphabetic())
+            && parsed.protocol.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+        if !valid_scheme || parsed.host.is_empty() || parsed.port.parse::<u16>().is_err() {
+            return Err(NanoGetError::new(ErrorKind::ParseError));
+        }
+        Ok(parsed)
+    }
+
+    /// Checks if a request can be made to the url, without connecting to it: the scheme is `http`
+    /// (or `https`, with the "https" feature enabled), the host is not empty and the port is a
+    /// valid, non-zero port number. The url must also be free of whitespace and control characters.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::Url;
+    /// assert!(Url::new("http://example.com:8080/").is_fetchable());
+    /// assert!(!Url::new("ftp://example.com/").is_fetchable());
+    /// assert!(!Url::new("http://example.com:99999/").is_fetchable());
+    /// ```
+    pub fn is_fetchable(&self) -> bool {
+        let supported = self.protocol.eq_ignore_ascii_case("http")
+            || (cfg!(feature = "https") && self.protocol.eq_ignore_ascii_case("https"));
+        let clean = |s: &str| !s.chars().any(|c| c.is_whitespace() || c.is_control());
+        supported && !self.host.is_empty() && clean(&self.host) && clean(&self.path)
+            && self.port.parse::<u16>().is_ok_and(|port| port != 0)
+    }
+
+    /// Parses each of the given urls using `Url::parse`, keeping the results in order.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::Url;
+    /// let urls = Url::parse_many(vec!["http://example.com/", "http://:80/"]);
+    /// assert!(urls[0].is_ok());
+    /// assert!(urls[1].is_err());
+    /// ```
+    pub fn parse_many<I, S>(urls: I) -> Vec<Result<Url, NanoGetError>>
+        where I: IntoIterator<Item=S>, S: AsRef<str> {
+        urls.into_iter().map(|url| Url::parse(url.as_ref())).collect()
+    }
+
+    /// Creates a http url for the given host and port, with the path `/`.
+    ///
+    /// Handy for local testing, without having to format a url string. IPv6 addresses are
+    /// enclosed in brackets.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::Url;
+    /// let url = Url::from_host_port("127.0.0.1", 3000);
+    /// assert_eq!(url.get_host_with_port(), "127.0.0.1:3000");
+    /// ```
+    pub fn from_host_port(host: &str, port: u16) -> Self {
+        let host = if host.contains(':') && !host.starts_with('[') {
+            format!("[{}]", host)
+        } else {
+            host.to_string()
+        };
+        let absolute = format!("http://{}:{}/", host, port);
+        Url {
+            protocol: "http".to_string(),
+            host,
+            port: port.to_string(),
+            path: "/".to_string(),
+            _absolute: absolute,
+        }
+    }
+
+    /// Returns a copy of the url with the scheme replaced, or an error of kind `ParseError` if the
+    /// scheme is not valid.
+    ///
+    /// If the port is the default port of the current scheme, it is changed to the default port
+    /// of the new one. Any other port is kept as it is.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::Url;
+    /// let url = Url::new("http://example.com/a?b=1").with_scheme("https").unwrap();
+    /// assert_eq!(url.get_full_url(), "https://example.com:443/a?b=1");
+    /// let url = Url::new("http://example.com:8080/").with_scheme("https").unwrap();
+    /// assert_eq!(url.port, "8080");
+    /// ```
+    pub fn with_scheme(&self, scheme: &str) -> Result<Url, NanoGetError> {
+        let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+        if !valid_scheme {
+            return Err(NanoGetError::new(ErrorKind::ParseError));
+        }
+        let mut url = self.clone();
+        url.protocol = scheme.to_ascii_lowercase();
+        let old_default = Self::get_default_port_for_proto(&self.protocol.to_ascii_lowercase());
+        if old_default.as_ref() == Some(&self.port) {
+            if let Some(port) = Self::get_default_port_for_proto(&url.protocol) {
+                url.port = port;
+            }
+        }
+        url._absolute = url.get_origin() + &url.path;
+        Ok(url)
+    }
+
     fn get_default_port_for_proto(proto: &str) -> Option<String> {
         match proto {
             "http" => Some("80".to_string()),
@@ -53,10 +172,218 @@ impl Url {
         self.protocol.clone() + "://" + &self.host + ":" + &self.port + &self.path
     }
 
+    /// Returns the url exactly as it was given when this Url was created.
+    ///
+    /// Unlike `get_full_url`, this round-trips: a url without a port is returned without one.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::Url;
+    /// let url = Url::new("HTTP://Example.com/a/../b");
+    /// assert_eq!(url.as_absolute(), "HTTP://Example.com/a/../b");
+    /// ```
+    pub fn as_absolute(&self) -> &str {
+        &self._absolute
+    }
+
+    /// Rebuilds the url in its canonical form.
+    ///
+    /// The scheme and host are lowercased, the default port for the scheme is left out, dot
+    /// segments are removed from the path and any fragment is dropped.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::Url;
+    /// let url = Url::new("HTTP://Example.com:80/a/../b?c=1#top");
+    /// assert_eq!(url.normalized(), "http://example.com/b?c=1");
+    /// ```
+    pub fn normalized(&self) -> String {
+        let mut url = self.clone();
+        url.protocol = url.protocol.to_ascii_lowercase();
+        url.host = url.host.to_ascii_lowercase();
+        let path = self.path.split('#').next().unwrap_or("");
+        let (path, query) = match path.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (path, None),
+        };
+        let path = if path.is_empty() { "/".to_string() } else { remove_dot_segments(path) };
+        match query {
+            Some(query) => format!("{}{}?{}", url.get_origin(), path, query),
+            None => url.get_origin() + &path,
+        }
+    }
+
     /// returns the host:port of the url.
     pub fn get_host_with_port(&self) -> String {
         self.host.clone() + ":" + &self.port
     }
+
+    /// Resolves a reference (like the value of a `Location` header) against this url.
+    ///
+    /// The reference may be an absolute url, a scheme-relative url (`//host/path`), an absolute
+    /// path (`/path`), a query (`?a=1`) or a path relative to this url. Any fragment is dropped.
+    ///
+    /// Returns an error of kind `ParseError` if the reference contains whitespace or control characters.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::Url;
+    /// let base = Url::new("http://example.com/a/b?c=1");
+    /// assert_eq!(base.join("d").unwrap().path, "/a/d");
+    /// assert_eq!(base.join("../d").unwrap().path, "/d");
+    /// assert_eq!(base.join("https://example.org/").unwrap().host, "example.org");
+    /// ```
+    pub fn join(&self, reference: &str) -> Result<Url, NanoGetError> {
+        let reference = reference.trim();
+        if reference.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(NanoGetError::new(ErrorKind::ParseError));
+        }
+        let reference = reference.split('#').next().unwrap_or("");
+        if has_scheme(reference) {
+            return Ok(Url::new(reference));
+        }
+        if reference.starts_with("//") {
+            return Ok(Url::new(&format!("{}:{}", self.protocol, reference)));
+        }
+        let base_path = self.path.split('?').next().unwrap_or("/");
+        let path = if reference.is_empty() {
+            self.path.clone()
+        } else if reference.starts_with('/') {
+            remove_dot_segments(reference)
+        } else if reference.starts_with('?') {
+            base_path.to_string() + reference
+        } else {
+            let dir = base_path.rfind('/').map_or("/", |i| &base_path[..=i]);
+            remove_dot_segments(&(dir.to_string() + reference))
+        };
+        Ok(Url::new(&(self.get_origin() + &path)))
+    }
+
+    /// Returns the raw query of the url (everything after the `?`), if present.
+    pub fn query(&self) -> Option<&str> {
+        let query = self.path.split_once('?')?.1;
+        Some(query.split('#').next().unwrap_or(query))
+    }
+
+    /// Returns the decoded key-value pairs of the query, in the order they appear in the url.
+    ///
+    /// A `+` is kept as a literal plus sign, as per RFC 3986.
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        self.query().map_or_else(Vec::new, |query| {
+            query.split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| {
+                    let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+                    (decode_component(k), decode_component(v))
+                })
+                .collect()
+        })
+    }
+
+    /// Appends a key-value pair to the query of the url, percent-encoding both.
+    ///
+    /// Pairs are kept in the order they are added. Any fragment is dropped, as it is never sent to
+    /// the server and the query has to come before it.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::Url;
+    /// let mut url = Url::new("http://example.com/search");
+    /// url.add_query_param("q", "rust lang");
+    /// url.add_query_param("page", "2");
+    /// assert_eq!(url.path, "/search?q=rust%20lang&page=2");
+    /// ```
+    pub fn add_query_param(&mut self, key: &str, value: &str) {
+        let separator = match self.query() {
+            None => "?",
+            Some("") => "",
+            Some(_) => "&",
+        };
+        if let Some(fragment) = self.path.find('#') {
+            self.path.truncate(fragment);
+        }
+        self.path = format!("{}{}{}={}", self.path, separator, encode_query_component(key), encode_query_component(value));
+        self._absolute = self.get_origin() + &self.path;
+    }
+
+    /// Replaces the path of the url, keeping its query.
+    ///
+    /// The characters that are not allowed in a path (like spaces, `?` and `#`) are
+    /// percent-encoded, while `/` separators and existing escapes are kept. A `/` is added in front
+    /// if missing. Any fragment is dropped, as it is never sent to the server.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::Url;
+    /// let mut url = Url::new("http://example.com/old?page=2");
+    /// url.set_path("users/jane doe");
+    /// assert_eq!(url.path, "/users/jane%20doe?page=2");
+    /// ```
+    pub fn set_path(&mut self, path: &str) {
+        let query = self.query().map(|query| format!("?{}", query)).unwrap_or_default();
+        let separator = if path.starts_with('/') { "" } else { "/" };
+        self.path = format!("{}{}{}", separator, encode_path(path), query);
+        self._absolute = self.get_origin() + &self.path;
+    }
+
+    /// Replaces the query of the url with the given key-value pairs, percent-encoding both (see
+    /// `add_query_param`). The query is removed if there are no pairs. Any fragment is dropped.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::Url;
+    /// let mut url = Url::new("http://example.com/search?q=old");
+    /// url.set_query(&[("q", "rust lang"), ("page", "2")]);
+    /// assert_eq!(url.path, "/search?q=rust%20lang&page=2");
+    /// url.set_query(&[]);
+    /// assert_eq!(url.path, "/search");
+    /// ```
+    pub fn set_query(&mut self, pairs: &[(&str, &str)]) {
+        let end = self.path.find(['?', '#']).unwrap_or(self.path.len());
+        self.path.truncate(end);
+        self._absolute = self.get_origin() + &self.path;
+        for (key, value) in pairs {
+            self.add_query_param(key, value);
+        }
+    }
+
+    /// Returns the canonical form of the query, as used for signing requests (like AWS Signature V4).
+    ///
+    /// The pairs are decoded and then re-encoded with only the unreserved characters left as is
+    /// (spaces as `%20`, uppercase hex digits), sorted by key and then by value, and joined with `&`.
+    /// The result is the same no matter how the query was originally encoded or ordered.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use nano_get::Url;
+    /// let url = Url::new("http://example.com/?b=2&a=x%2fy&a=1&c");
+    /// assert_eq!(url.canonical_query(), "a=1&a=x%2Fy&b=2&c=");
+    /// ```
+    pub fn canonical_query(&self) -> String {
+        let mut pairs: Vec<(String, String)> = self.query_pairs().iter()
+            .map(|(k, v)| (encode_query_component(k), encode_query_component(v)))
+            .collect();
+        pairs.sort();
+        pairs.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<String>>().join("&")
+    }
+
+    fn get_origin(&self) -> String {
+        match Self::get_default_port_for_proto(&self.protocol) {
+            Some(ref port) if port == &self.port => self.protocol.clone() + "://" + &self.host,
+            _ => self.protocol.clone() + "://" + &self.get_host_with_port(),
+        }
+    }
+}
+
+/// Checks if the reference starts with a scheme (`ALPHA *( ALPHA / DIGIT / "+" / "-" / "." ) ":"`),
+/// which makes it an absolute url. A `:` after the first `/`, `?` or `#` (like in a url in the
+/// query) does not count.
+fn has_scheme(reference: &str) -> bool {
+    let end = reference.find(['/', '?', '#']).unwrap_or(reference.len());
+    reference[..end].split_once(':').is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    })
 }
 
 /// Represents the ability to be made into a URL.